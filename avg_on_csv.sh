@@ -1 +1 @@
-awk -F, '{ sum += $2; count += 1 } END { if (count > 0) printf "%.10f\n", sum / count }'
+awk -F, '$3 == "" || $3 == "OK" { sum += $2; count += 1 } END { if (count > 0) printf "%.10f\n", sum / count }'
@@ -0,0 +1,264 @@
+use crate::measurement::{Measurement, ReadingState};
+use chrono::prelude::*;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::process::{Child, Command};
+use std::thread;
+
+/// Condition which trips an alarm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlarmKind {
+    High,
+    Low,
+    Rate,
+    Stale,
+}
+
+impl AlarmKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlarmKind::High => "HIGH",
+            AlarmKind::Low => "LOW",
+            AlarmKind::Rate => "RATE",
+            AlarmKind::Stale => "STALE",
+        }
+    }
+}
+
+/// Alarm thresholds of a channel, in the unit of the channel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AlarmConfig {
+    pub high: Option<f64>,
+    pub low: Option<f64>,
+    pub rate: Option<f64>, // Maximum change of the value per second
+    pub stale: bool,       // Alarm when the reading goes stale
+}
+
+impl AlarmConfig {
+    /// Accepts comma separated conditions, i.e. `high=5.25,low=4.75,rate=0.5,stale`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = AlarmConfig::default();
+        for condition in text.split(',').map(|condition| condition.trim()) {
+            let invalid = || {
+                format!(
+                    "Invalid alarm condition '{}', expected high=<value>, low=<value>, rate=<value per second> or stale",
+                    condition
+                )
+            };
+            if condition == "stale" {
+                config.stale = true;
+                continue;
+            }
+            let (name, value) = condition.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().parse::<f64>().map_err(|_| invalid())?;
+            match name.trim() {
+                "high" => config.high = Some(value),
+                "low" => config.low = Some(value),
+                "rate" => config.rate = Some(value.abs()),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Alarm state of a channel. A tripped alarm stays latched (even when the condition is gone)
+/// until it is acknowledged.
+pub struct Alarm {
+    pub config: AlarmConfig,
+    active: Vec<AlarmKind>,  // Conditions present at the last check
+    latched: Vec<AlarmKind>, // Tripped and not acknowledged
+    last_valid: Option<(DateTime<Utc>, f64)>, // For the rate of change
+}
+
+impl Alarm {
+    pub fn new(config: AlarmConfig) -> Self {
+        Self {
+            config,
+            active: vec![],
+            latched: vec![],
+            last_valid: None,
+        }
+    }
+
+    /// Evaluates conditions with a newly received measurement (if any) and returns alarms
+    /// tripped just now; a condition which lasts trips only once.
+    pub fn check(&mut self, measurement: Option<&Measurement>, stale: bool) -> Vec<AlarmKind> {
+        let mut active: Vec<AlarmKind> = match measurement {
+            Some(m) => self.conditions(m),
+            // Without a new reading only staleness can change
+            None => self
+                .active
+                .iter()
+                .copied()
+                .filter(|kind| *kind != AlarmKind::Stale)
+                .collect(),
+        };
+        if self.config.stale && stale {
+            active.push(AlarmKind::Stale);
+        }
+
+        let tripped: Vec<AlarmKind> = active
+            .iter()
+            .copied()
+            .filter(|kind| !self.active.contains(kind))
+            .collect();
+        for kind in &tripped {
+            if !self.latched.contains(kind) {
+                self.latched.push(*kind);
+            }
+        }
+        self.active = active;
+        tripped
+    }
+
+    fn conditions(&mut self, measurement: &Measurement) -> Vec<AlarmKind> {
+        let mut conditions = vec![];
+        let valid = measurement.is_valid();
+        let high = match measurement.state {
+            ReadingState::OverloadPositive => self.config.high.is_some(),
+            _ => matches!(self.config.high, Some(high) if valid && measurement.value > high),
+        };
+        if high {
+            conditions.push(AlarmKind::High);
+        }
+        let low = match measurement.state {
+            ReadingState::OverloadNegative => self.config.low.is_some(),
+            _ => matches!(self.config.low, Some(low) if valid && measurement.value < low),
+        };
+        if low {
+            conditions.push(AlarmKind::Low);
+        }
+        if valid {
+            if let (Some(max_rate), Some((last_time, last_value))) =
+                (self.config.rate, self.last_valid)
+            {
+                let seconds =
+                    (measurement.timestamp - last_time).num_milliseconds() as f64 / 1000.0;
+                if seconds > 0.0 && ((measurement.value - last_value) / seconds).abs() > max_rate {
+                    conditions.push(AlarmKind::Rate);
+                }
+            }
+            self.last_valid = Some((measurement.timestamp, measurement.value));
+        }
+        conditions
+    }
+
+    pub fn latched(&self) -> &[AlarmKind] {
+        &self.latched
+    }
+
+    pub fn is_latched(&self) -> bool {
+        !self.latched.is_empty()
+    }
+
+    /// Clears latched alarms; conditions still present trip again only after they are gone.
+    pub fn acknowledge(&mut self) -> bool {
+        let was_latched = self.is_latched();
+        self.latched.clear();
+        was_latched
+    }
+}
+
+/// Notifies the outside world about tripped alarms: runs a shell command and/or writes
+/// a line to a file or FIFO (i.e. read by a script which switches off a power supply).
+pub struct AlarmHook {
+    command: Option<String>,
+    fifo: Option<String>,
+    children: Vec<Child>,
+}
+
+impl AlarmHook {
+    pub fn new(command: Option<String>, fifo: Option<String>) -> Self {
+        Self {
+            command,
+            fifo,
+            children: vec![],
+        }
+    }
+
+    /// Changes the command and FIFO, commands still running are reaped as before.
+    pub fn configure(&mut self, command: Option<String>, fifo: Option<String>) {
+        self.command = command;
+        self.fifo = fifo;
+    }
+
+    /// Runs the hook without waiting for it; the command gets `OMM_CHANNEL`, `OMM_ALARM`
+    /// and `OMM_VALUE` environment variables.
+    pub fn fire(&mut self, channel_no: u32, kind: AlarmKind, value: Option<f64>) {
+        let value = value.map(|value| value.to_string()).unwrap_or_default();
+        if let Some(command) = &self.command {
+            match Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("OMM_CHANNEL", channel_no.to_string())
+                .env("OMM_ALARM", kind.name())
+                .env("OMM_VALUE", &value)
+                .spawn()
+            {
+                Ok(child) => self.children.push(child),
+                Err(e) => eprintln!("Failed to run alarm command \"{}\": {}", command, e),
+            }
+        }
+        if let Some(fifo) = &self.fifo {
+            let fifo = fifo.clone();
+            let line = format!(
+                "{},{},{},{}\n",
+                Utc::now().timestamp_millis(),
+                channel_no,
+                kind.name(),
+                value
+            );
+            // Opening a FIFO blocks until there is a reader, so the display is not frozen by it
+            thread::spawn(move || {
+                let written = OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(&fifo)
+                    .and_then(|mut file| file.write_all(line.as_bytes()));
+                if let Err(e) = written {
+                    eprintln!("Failed to write alarm to \"{}\": {}", fifo, e);
+                }
+            });
+        }
+    }
+
+    /// Collects finished commands, so they do not stay as zombie processes.
+    pub fn reap(&mut self) {
+        self.children
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_alarm_conditions() {
+        assert_eq!(
+            AlarmConfig::parse("high=5.25, low=4.75,rate=-0.5,stale"),
+            Ok(AlarmConfig {
+                high: Some(5.25),
+                low: Some(4.75),
+                rate: Some(0.5),
+                stale: true,
+            })
+        );
+        assert_eq!(
+            AlarmConfig::parse("stale"),
+            Ok(AlarmConfig {
+                stale: true,
+                ..AlarmConfig::default()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_alarm_conditions() {
+        assert!(AlarmConfig::parse("high").is_err());
+        assert!(AlarmConfig::parse("high=x").is_err());
+        assert!(AlarmConfig::parse("peak=1").is_err());
+        assert!(AlarmConfig::parse("").is_err());
+    }
+}
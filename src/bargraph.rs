@@ -0,0 +1,200 @@
+use raylib::prelude::*;
+use std::time::{Duration, Instant};
+
+const SEGMENT_COUNT: usize = 50;
+const SEGMENT_GAP_RATIO: f32 = 0.25;
+const GHOST_ALPHA: f32 = 0.08;
+const RESPONSE_PER_SEC: f32 = 20.0; // How fast the bar follows the value
+const PEAK_HOLD_MILISEC: u64 = 2000;
+
+/// Full scale of the bar graph.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarScale {
+    /// Decade range of the value, i.e. 0..10 for 4.2 (`zero_centered`: -10..10).
+    Auto { zero_centered: bool },
+    /// Fixed `min..max`; the bar grows from zero when it is inside the range.
+    Fixed { min: f64, max: f64 },
+}
+
+impl BarScale {
+    /// Accepts `auto`, `auto_zero` (zero-centered) or `<min>,<max>`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "auto" => Ok(BarScale::Auto {
+                zero_centered: false,
+            }),
+            "auto_zero" => Ok(BarScale::Auto {
+                zero_centered: true,
+            }),
+            text => {
+                let invalid = || {
+                    format!(
+                        "Invalid bar graph scale '{}', expected auto, auto_zero or <min>,<max>",
+                        text
+                    )
+                };
+                let (min, max) = text.split_once(',').ok_or_else(invalid)?;
+                let min = min.trim().parse::<f64>().map_err(|_| invalid())?;
+                let max = max.trim().parse::<f64>().map_err(|_| invalid())?;
+                if min >= max {
+                    return Err(invalid());
+                }
+                Ok(BarScale::Fixed { min, max })
+            }
+        }
+    }
+
+    /// Range before the first value (auto scale: 0..1, zero-centered -1..1).
+    pub fn initial_range(&self) -> (f64, f64) {
+        match *self {
+            BarScale::Auto { zero_centered } => (if zero_centered { -1.0 } else { 0.0 }, 1.0),
+            BarScale::Fixed { min, max } => (min, max),
+        }
+    }
+
+    /// Decade range of the value for the auto scale; `None` keeps the current range (fixed
+    /// scale, zero, overload or a missing value, which have no decade).
+    pub fn range_for(&self, value: f64) -> Option<(f64, f64)> {
+        match *self {
+            BarScale::Auto { zero_centered } if value.is_finite() && value != 0.0 => {
+                let full_scale = 10f64.powf(value.abs().log10().ceil());
+                Some((if zero_centered { -full_scale } else { 0.0 }, full_scale))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Analog bar graph like the one under the digits of handheld meters. It is animated every
+/// frame, independently of how often the digits change.
+pub struct BarGraph {
+    scale: BarScale,
+    range: (f64, f64),
+    level: f64, // Value shown by the bar, following the reading
+    peaks: Option<((f64, Instant), (f64, Instant))>, // Highest and lowest value with the time
+}
+
+impl BarGraph {
+    pub fn new(scale: BarScale) -> Self {
+        Self {
+            scale,
+            range: scale.initial_range(),
+            level: 0.0,
+            peaks: None,
+        }
+    }
+
+    /// Moves the bar towards the value (overload as infinity), `None` keeps it in place.
+    pub fn update(&mut self, value: Option<f64>, frame_time: f32) {
+        let value = match value {
+            Some(value) if !value.is_nan() => value,
+            _ => return,
+        };
+        if let Some(range) = self.scale.range_for(value) {
+            self.range = range;
+        }
+        let value = value.clamp(self.range.0, self.range.1);
+        self.level += (value - self.level) * (frame_time * RESPONSE_PER_SEC).min(1.0) as f64;
+
+        let now = Instant::now();
+        let hold = Duration::from_millis(PEAK_HOLD_MILISEC);
+        let ((mut high, mut high_time), (mut low, mut low_time)) =
+            self.peaks.unwrap_or(((value, now), (value, now)));
+        if value >= high || high_time.elapsed() > hold {
+            (high, high_time) = (value, now);
+        }
+        if value <= low || low_time.elapsed() > hold {
+            (low, low_time) = (value, now);
+        }
+        self.peaks = Some(((high, high_time), (low, low_time)));
+    }
+
+    /// Position of the value in the range, 0.0 (min) to 1.0 (max).
+    fn position(&self, value: f64) -> f32 {
+        let (min, max) = self.range;
+        ((value - min) / (max - min)).clamp(0.0, 1.0) as f32
+    }
+
+    /// Draws segments in the area; lit ones go from zero (or the range start) to the value.
+    pub fn draw(&self, d: &mut RaylibDrawHandle<'_>, area: Rectangle, color: Color) {
+        let origin = self.position(0.0);
+        let level = self.position(self.level);
+        let (from, to) = if level < origin {
+            (level, origin)
+        } else {
+            (origin, level)
+        };
+        let peak_segments: Vec<usize> = match self.peaks {
+            Some(((high, _), (low, _))) => vec![self.segment(high), self.segment(low)],
+            None => vec![],
+        };
+
+        let pitch = area.width / SEGMENT_COUNT as f32;
+        let ghost_color = color.fade(GHOST_ALPHA);
+        for i in 0..SEGMENT_COUNT {
+            let center = (i as f32 + 0.5) / SEGMENT_COUNT as f32;
+            let lit = (from..=to).contains(&center) || peak_segments.contains(&i);
+            // Scale ticks: longer segments at the ends and in the middle
+            let tall = i == 0 || i == SEGMENT_COUNT - 1 || i == SEGMENT_COUNT / 2;
+            let height = if tall { area.height } else { area.height * 0.7 };
+            d.draw_rectangle_v(
+                Vector2::new(area.x + i as f32 * pitch, area.y + area.height - height),
+                Vector2::new(pitch * (1.0 - SEGMENT_GAP_RATIO), height),
+                if lit { color } else { ghost_color },
+            );
+        }
+    }
+
+    fn segment(&self, value: f64) -> usize {
+        ((self.position(value) * SEGMENT_COUNT as f32) as usize).min(SEGMENT_COUNT - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTO: BarScale = BarScale::Auto {
+        zero_centered: false,
+    };
+    const AUTO_ZERO: BarScale = BarScale::Auto {
+        zero_centered: true,
+    };
+
+    #[test]
+    fn finds_decade_range_of_value() {
+        assert_eq!(AUTO.range_for(4.2), Some((0.0, 10.0)));
+        assert_eq!(AUTO.range_for(10.0), Some((0.0, 10.0)));
+        assert_eq!(AUTO.range_for(10.5), Some((0.0, 100.0)));
+        assert_eq!(AUTO.range_for(-420.0), Some((0.0, 1000.0)));
+        assert_eq!(AUTO_ZERO.range_for(-3.0), Some((-10.0, 10.0)));
+        let (min, max) = AUTO.range_for(0.042).unwrap();
+        assert!(min == 0.0 && (max - 0.1).abs() < 1e-15);
+    }
+
+    #[test]
+    fn keeps_range_without_decade() {
+        for value in [0.0, -0.0, f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+            assert_eq!(AUTO.range_for(value), None);
+            assert_eq!(AUTO_ZERO.range_for(value), None);
+        }
+        assert_eq!(
+            BarScale::Fixed {
+                min: -5.0,
+                max: 5.0
+            }
+            .range_for(42.0),
+            None
+        );
+    }
+
+    #[test]
+    fn starts_with_unit_or_fixed_range() {
+        assert_eq!(AUTO.initial_range(), (0.0, 1.0));
+        assert_eq!(AUTO_ZERO.initial_range(), (-1.0, 1.0));
+        assert_eq!(
+            BarScale::parse("-5,5").unwrap().initial_range(),
+            (-5.0, 5.0)
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use crate::alarm::{Alarm, AlarmConfig};
+use crate::bargraph::{BarGraph, BarScale};
+use crate::chart::{ChartNavigation, ChartView};
+use crate::format::Resolution;
+use crate::gauge::Gauge;
+use crate::history::History;
+use crate::limits::{Limits, Zone};
+use crate::measurement::{Measurement, ReadingState, Unit};
+use crate::statistics::Statistics;
+use crate::{Histogram, ReadingHealth};
+use chrono::Utc;
+use serialport::SerialPort;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+const SERIAL_BUFFER_SIZE: usize = 32;
+const SERIAL_TIMEOUT_MILISEC: u64 = 10;
+const SERIAL_RECONNECT_INTERVAL_MILISEC: u64 = 2000;
+const SCPI_MEAS_CMD_OWON: &[u8; 6] = b"MEAS?\n";
+const CHART_MODES: [&str; 3] = ["", "h", "l"];
+
+/// Serial port and unit of a single measurement channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelConfig {
+    pub port_name: String,
+    pub baud_rate: u32,
+    pub channel_no: u32,
+    pub unit: Unit,
+    pub limits: Option<Limits>, // Colors of the value by zone, when set
+    pub alarm: AlarmConfig,
+    pub resolution: Option<Resolution>, // Display resolution, when set
+    pub bar_scale: Option<BarScale>,    // Bar graph under the digits, when set
+    pub gauge_scale: Option<BarScale>,  // Analog gauge instead of the digits, when set
+    pub digits_update: Duration,        // Minimum time between changes of the digits
+}
+
+/// User actions on a channel (hotkeys, buttons and touch buttons).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelAction {
+    Hold,
+    Rel,
+    Reset,
+    Pause,
+    Chart(&'static str), // h (histogram), l (linear) or empty (no chart)
+    CycleChart,
+    CycleChartWindow,
+    AckAlarm,
+}
+
+/// Collects bytes from the serial port and hands out only complete (`\n` terminated) lines,
+/// so a partially received value is never displayed.
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn new() -> Self {
+        Self { pending: vec![] }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.pending.extend(bytes.iter().filter(|&&b| b != 0));
+        // Protect against a device which never sends a line separator
+        if self.pending.len() > SERIAL_BUFFER_SIZE * 4 {
+            self.pending.clear();
+        }
+    }
+
+    /// Returns the most recent complete line (without line separators) and drops older ones.
+    fn last_complete_line(&mut self) -> Option<Vec<u8>> {
+        let end = self.pending.iter().rposition(|&b| b == b'\n')?;
+        let complete: Vec<u8> = self.pending.drain(..=end).collect();
+        complete
+            .split(|&b| b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .rfind(|line| !line.is_empty())
+            .map(|line| line.to_vec())
+    }
+}
+
+/// Acquisition state of a single meter: serial port (re-opened after disconnects), received
+/// measurements and data for charts.
+pub struct Channel {
+    pub config: ChannelConfig,
+    port: Option<Box<dyn SerialPort>>,
+    last_open_attempt: Option<Instant>,
+    line_buffer: LineBuffer,
+    pub health: ReadingHealth,
+    pub measurement: Option<Measurement>,
+    digits: Option<Measurement>, // Measurement shown by the digits, updated at most every `digits_update`
+    digits_updated: Option<Instant>,
+    held: Option<Measurement>, // Measurement frozen on the display by HOLD
+    rel_offset: Option<f64>,   // Reference value subtracted from displayed value by REL
+    paused: Option<f64>,       // Chart time of the pause, received readings are dropped
+    pub histogram: Histogram,
+    pub statistics: Statistics,
+    pub history: History,
+    pub chart: String, // Chart type: h (histogram), l (linear) or empty
+    pub chart_view: ChartView,
+    pub chart_navigation: ChartNavigation,
+    pub bargraph: Option<BarGraph>,
+    pub gauge: Option<Gauge>,
+    pub alarm: Alarm,
+}
+
+impl Channel {
+    pub fn new(
+        config: ChannelConfig,
+        chart: &str,
+        chart_view: ChartView,
+        stale_timeout: Duration,
+    ) -> Self {
+        Self {
+            alarm: Alarm::new(config.alarm.clone()),
+            bargraph: config.bar_scale.map(BarGraph::new),
+            gauge: config.gauge_scale.map(Gauge::new),
+            config,
+            port: None,
+            last_open_attempt: None,
+            line_buffer: LineBuffer::new(),
+            health: ReadingHealth::new(stale_timeout),
+            measurement: None,
+            digits: None,
+            digits_updated: None,
+            held: None,
+            rel_offset: None,
+            paused: None,
+            // Adjust min, max, and bin_count as needed
+            histogram: Histogram::new(0.0, 10.0, 50),
+            statistics: Statistics::new(),
+            history: History::new(),
+            chart: chart.to_string(),
+            chart_view,
+            chart_navigation: ChartNavigation::new(),
+        }
+    }
+
+    pub fn open(&mut self) -> Result<(), String> {
+        self.last_open_attempt = Some(Instant::now());
+        let port = serialport::new(&self.config.port_name, self.config.baud_rate)
+            .timeout(Duration::from_millis(SERIAL_TIMEOUT_MILISEC))
+            .open()
+            .map_err(|e| {
+                format!(
+                    "Failed to open serial port \"{}\": {}",
+                    self.config.port_name, e
+                )
+            })?;
+        self.port = Some(port);
+        self.health.record_connected();
+        Ok(())
+    }
+
+    fn disconnect(&mut self, reason: String) {
+        eprintln!("{} (port \"{}\")", reason, self.config.port_name);
+        self.port = None;
+        self.health.connected = false;
+    }
+
+    /// Reads available data from the port (re-opening it when disconnected) and returns a newly
+    /// received measurement.
+    pub fn poll(&mut self, scpi_protocol_enabled: bool) -> Option<Measurement> {
+        let reconnect_due = match self.last_open_attempt {
+            Some(attempt) => {
+                attempt.elapsed() > Duration::from_millis(SERIAL_RECONNECT_INTERVAL_MILISEC)
+            }
+            None => true,
+        };
+        if self.port.is_none() && reconnect_due {
+            if let Err(e) = self.open() {
+                eprintln!("{}", e);
+            }
+        }
+
+        let mut serial_buf: Vec<u8> = vec![0; SERIAL_BUFFER_SIZE];
+        let port = self.port.as_mut()?;
+        if scpi_protocol_enabled && self.paused.is_none() {
+            match port.write(SCPI_MEAS_CMD_OWON) {
+                Ok(_) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::TimedOut => (),
+                Err(e) => {
+                    self.disconnect(format!(
+                        "Error writing data to port (after SCPI cmd.): {:?}",
+                        e
+                    ));
+                    self.health.update();
+                    return None;
+                }
+            }
+        }
+
+        match port.read(serial_buf.as_mut_slice()) {
+            Ok(bytes_read) => self.line_buffer.push(&serial_buf[..bytes_read]),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => (),
+            Err(e) => self.disconnect(format!("Error reading data from port: {:?}", e)),
+        }
+
+        if self.is_paused() {
+            // Port is still read, so old readings do not pile up until acquisition is resumed
+            self.line_buffer.last_complete_line();
+            return None;
+        }
+
+        let mut new_measurement = None;
+        if let Some(line) = self.line_buffer.last_complete_line() {
+            match Measurement::parse(&line, &self.config.unit) {
+                Ok(parsed) => {
+                    self.health.record_valid();
+                    // Overload and invalid readings are not measurements, keep them out of statistics
+                    if parsed.is_valid() {
+                        self.histogram.add_value(parsed.value);
+                        self.statistics.add_value(parsed.value);
+                        self.history.add(
+                            parsed.timestamp.timestamp_millis() as f64 / 1000.0,
+                            parsed.value,
+                        );
+                    }
+                    let digits_due = match self.digits_updated {
+                        Some(updated) => updated.elapsed() >= self.config.digits_update,
+                        None => true,
+                    };
+                    if digits_due {
+                        self.digits = Some(parsed.clone());
+                        self.digits_updated = Some(Instant::now());
+                    }
+                    self.measurement = Some(parsed.clone());
+                    new_measurement = Some(parsed);
+                }
+                Err(e) => {
+                    eprintln!("Error: {:?}", e);
+                    self.health.record_parse_failure();
+                }
+            }
+        }
+        self.health.update();
+        new_measurement
+    }
+
+    pub fn apply(&mut self, action: ChannelAction) {
+        match action {
+            ChannelAction::Hold => {
+                self.held = match self.held {
+                    Some(_) => None,
+                    None => self.digits.clone(),
+                }
+            }
+            ChannelAction::Rel => {
+                self.rel_offset = match self.rel_offset {
+                    Some(_) => None,
+                    None => self
+                        .measurement
+                        .as_ref()
+                        .filter(|m| m.is_valid())
+                        .map(|m| m.value),
+                }
+            }
+            ChannelAction::Reset => {
+                self.histogram.reset();
+                self.statistics.reset();
+                self.history.clear();
+                self.chart_navigation = ChartNavigation::new();
+            }
+            ChannelAction::Pause => {
+                self.paused = match self.paused {
+                    Some(_) => None,
+                    None => Some(Utc::now().timestamp_millis() as f64 / 1000.0),
+                }
+            }
+            ChannelAction::Chart(mode) => self.chart = mode.to_string(),
+            ChannelAction::CycleChart => {
+                let current = CHART_MODES
+                    .iter()
+                    .position(|mode| *mode == self.chart)
+                    .unwrap_or(0);
+                self.chart = CHART_MODES[(current + 1) % CHART_MODES.len()].to_string();
+            }
+            ChannelAction::CycleChartWindow => {
+                self.chart_view.window = self.chart_view.window.next()
+            }
+            ChannelAction::AckAlarm => {
+                if self.alarm.acknowledge() {
+                    eprintln!("Alarm acknowledged on CH:{}", self.config.channel_no);
+                }
+            }
+        }
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held.is_some()
+    }
+
+    pub fn is_rel(&self) -> bool {
+        self.rel_offset.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Present time of the linear chart (seconds since the UNIX epoch), stopped while paused.
+    pub fn chart_time(&self) -> f64 {
+        self.paused
+            .unwrap_or_else(|| Utc::now().timestamp_millis() as f64 / 1000.0)
+    }
+
+    /// Zone of the shown measurement within the channel limits; limits are compared with
+    /// the measured value, not shifted by REL.
+    pub fn zone(&self) -> Option<Zone> {
+        let measured = self.held.as_ref().or(self.digits.as_ref())?;
+        self.config.limits?.zone(measured)
+    }
+
+    /// Measurement presented by the digits, with HOLD and REL applied.
+    pub fn shown_measurement(&self) -> Option<Measurement> {
+        self.present(self.digits.as_ref())
+    }
+
+    /// The most recent measurement (not slowed down like the digits), with HOLD and REL applied.
+    pub fn live_measurement(&self) -> Option<Measurement> {
+        self.present(self.measurement.as_ref())
+    }
+
+    fn present(&self, measurement: Option<&Measurement>) -> Option<Measurement> {
+        let mut shown = self.held.as_ref().or(measurement)?.clone();
+        shown.flags.hold = self.is_held();
+        if let Some(offset) = self.rel_offset {
+            shown.value -= offset;
+            shown.flags.rel = true;
+        }
+        Some(shown)
+    }
+
+    /// Animates the bar graph and the gauge needle towards the live measurement, called every frame.
+    pub fn animate(&mut self, frame_time: f32) {
+        let value = self.live_measurement().and_then(|m| match m.state {
+            ReadingState::Valid => Some(m.value),
+            ReadingState::OverloadPositive => Some(f64::INFINITY),
+            ReadingState::OverloadNegative => Some(f64::NEG_INFINITY),
+            _ => None,
+        });
+        if let Some(bargraph) = self.bargraph.as_mut() {
+            bargraph.update(value, frame_time);
+        }
+        if let Some(gauge) = self.gauge.as_mut() {
+            gauge.update(value, frame_time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_buffer_waits_for_complete_line() {
+        let mut buffer = LineBuffer::new();
+        buffer.push(b"1.23");
+        assert_eq!(buffer.last_complete_line(), None);
+        buffer.push(b"4\r\n5.6");
+        assert_eq!(buffer.last_complete_line(), Some(b"1.234".to_vec()));
+        assert_eq!(buffer.last_complete_line(), None);
+        buffer.push(b"7\n");
+        assert_eq!(buffer.last_complete_line(), Some(b"5.67".to_vec()));
+    }
+
+    #[test]
+    fn line_buffer_returns_latest_line() {
+        let mut buffer = LineBuffer::new();
+        buffer.push(b"1\n2\n3\n\n4");
+        assert_eq!(buffer.last_complete_line(), Some(b"3".to_vec()));
+    }
+
+    #[test]
+    fn line_buffer_drops_nul_bytes() {
+        let mut buffer = LineBuffer::new();
+        buffer.push(b"\x001.5\x00\n");
+        assert_eq!(buffer.last_complete_line(), Some(b"1.5".to_vec()));
+    }
+
+    #[test]
+    fn line_buffer_clears_line_without_separator() {
+        let mut buffer = LineBuffer::new();
+        buffer.push(&[b'1'; SERIAL_BUFFER_SIZE * 4 + 1]);
+        buffer.push(b"2\n");
+        assert_eq!(buffer.last_complete_line(), Some(b"2".to_vec()));
+    }
+}
@@ -0,0 +1,759 @@
+use crate::format::format_engineering;
+use crate::history::{ChartPoint, History};
+use crate::measurement::SiPrefix;
+use crate::theme::Theme;
+use raylib::prelude::*;
+use std::time::Duration;
+
+// Time between ticks of the time axis, the smallest one leaving enough space for labels is used
+const TIME_TICK_STEPS_SEC: [f64; 18] = [
+    1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 900.0, 1800.0, 3600.0, 7200.0,
+    10800.0, 21600.0, 43200.0, 86400.0,
+];
+const TIME_TICK_MIN_SPACING: f32 = 1.5; // In grid steps
+const RANGE_PADDING_RATIO: f64 = 0.1; // Space above and below values in the auto range
+const FLAT_RANGE_RATIO: f64 = 1e-3; // Auto range of a constant value, relative to the value
+const MIN_RANGE_RATIO: f64 = 1e-9; // Smallest range relative to its values, for round ticks
+const MAX_VALUE_TICKS: usize = 100;
+const LABEL_FONT_SIZE: i32 = 20;
+const MAX_POINTS: usize = 2000; // Drawn points, older readings are decimated to this count
+const FILL_ALPHA: f32 = 0.3; // Area under the line and the envelope band
+const ZOOM_STEP: f64 = 1.25; // Change of the shown time for a step of the mouse wheel
+const MIN_SHOWN_SEC: f64 = 1.0;
+const CLICK_MAX_MOVE: f32 = 4.0; // Mouse movement (pixels) of a click, longer one is a drag
+const CURSOR_NAMES: [&str; 2] = ["A", "B"];
+const READOUT_SIGNIFICANT_DIGITS: usize = 5;
+
+/// Time span shown by the linear chart, ending at the present.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartWindow {
+    Last(Duration),
+    All,
+}
+
+impl ChartWindow {
+    /// Windows switched through at runtime.
+    const PRESETS: [ChartWindow; 4] = [
+        ChartWindow::Last(Duration::from_secs(10)),
+        ChartWindow::Last(Duration::from_secs(60)),
+        ChartWindow::Last(Duration::from_secs(600)),
+        ChartWindow::All,
+    ];
+
+    /// Accepts `all` or a time with unit, i.e. `10s`, `1m`, `10m` or `2h`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid chart window '{}', expected all or a time like 10s, 1m, 10m, 2h",
+                text
+            )
+        };
+        match text.trim() {
+            "all" => Ok(ChartWindow::All),
+            text => parse_time(text).map(ChartWindow::Last).ok_or_else(invalid),
+        }
+    }
+
+    /// The next preset window (the first one after a custom window).
+    pub fn next(&self) -> Self {
+        match ChartWindow::PRESETS
+            .iter()
+            .position(|window| window == self)
+        {
+            Some(i) => ChartWindow::PRESETS[(i + 1) % ChartWindow::PRESETS.len()],
+            None => ChartWindow::PRESETS[0],
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            ChartWindow::Last(duration) => format!("last {}", format_time(duration.as_secs_f64())),
+            ChartWindow::All => "all".to_string(),
+        }
+    }
+}
+
+/// Value range of the linear chart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartRange {
+    /// Range of values in the time window with some padding (`zero_centered`: symmetric
+    /// around zero).
+    Auto { zero_centered: bool },
+    /// Fixed `min..max`, values outside are clipped.
+    Fixed { min: f64, max: f64 },
+}
+
+impl ChartRange {
+    /// Accepts `auto`, `auto_zero` (zero-centered) or `<min>,<max>`.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "auto" => Ok(ChartRange::Auto {
+                zero_centered: false,
+            }),
+            "auto_zero" => Ok(ChartRange::Auto {
+                zero_centered: true,
+            }),
+            text => {
+                let invalid = || {
+                    format!(
+                        "Invalid chart range '{}', expected auto, auto_zero or <min>,<max>",
+                        text
+                    )
+                };
+                let (min, max) = text.split_once(',').ok_or_else(invalid)?;
+                let min = min.trim().parse::<f64>().map_err(|_| invalid())?;
+                let max = max.trim().parse::<f64>().map_err(|_| invalid())?;
+                if !is_usable_range(min, max) {
+                    return Err(invalid());
+                }
+                Ok(ChartRange::Fixed { min, max })
+            }
+        }
+    }
+
+    /// Range `(min, max)` for the values (overloads are left out), never empty.
+    fn of(&self, values: &[f64]) -> (f64, f64) {
+        let mut values = values.iter().filter(|value| value.is_finite()).peekable();
+        let (min, max) = match self {
+            ChartRange::Fixed { min, max } => return (*min, *max),
+            ChartRange::Auto { .. } if values.peek().is_none() => (0.0, 1.0),
+            ChartRange::Auto { .. } => values
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                    (min.min(*value), max.max(*value))
+                }),
+        };
+        let padding = if max > min {
+            ((max - min) * RANGE_PADDING_RATIO).max(min.abs().max(max.abs()) * MIN_RANGE_RATIO)
+        } else if max != 0.0 {
+            max.abs() * FLAT_RANGE_RATIO
+        } else {
+            1.0
+        };
+        match self {
+            ChartRange::Auto {
+                zero_centered: true,
+            } => {
+                let limit = min.abs().max(max.abs()) + padding;
+                (-limit, limit)
+            }
+            _ => (min - padding, max + padding),
+        }
+    }
+}
+
+/// The range is finite and wide enough for distinct ticks of the value axis.
+fn is_usable_range(min: f64, max: f64) -> bool {
+    let span = max - min;
+    span.is_finite() && span > min.abs().max(max.abs()) * MIN_RANGE_RATIO
+}
+
+/// How readings are drawn on the linear chart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartStyle {
+    Points,
+    Line,
+    Step,
+    Area,     // Line filled down to zero (or the edge of the range)
+    Envelope, // Band from the lowest to the highest reading with the average line
+}
+
+impl ChartStyle {
+    pub const NAMES: [&'static str; 5] = ["points", "line", "step", "area", "envelope"];
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "points" => Ok(ChartStyle::Points),
+            "line" => Ok(ChartStyle::Line),
+            "step" => Ok(ChartStyle::Step),
+            "area" => Ok(ChartStyle::Area),
+            "envelope" => Ok(ChartStyle::Envelope),
+            text => Err(format!(
+                "Invalid chart style '{}', expected one of: {}",
+                text,
+                ChartStyle::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// How the linear chart of a channel is shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartView {
+    pub window: ChartWindow,
+    pub range: ChartRange,
+    pub style: ChartStyle,
+    pub average: Option<Duration>, // Period of the moving average line, when set
+}
+
+/// Where the linear chart is drawn, sizes of its parts (scaled with the window) and colors.
+pub struct ChartLayout<'a> {
+    pub area: Rectangle, // Plot area inside axis labels
+    pub point_circle_size: f32,
+    pub grid_step: f32,
+    pub point_color: &'a dyn Fn(f64) -> Color, // Color of a reading by its value
+    pub theme: &'a Theme,
+}
+
+/// Zoom, pan and measurement cursors of the linear chart, changed with the mouse over it: the
+/// wheel zooms, dragging pans, a click places a cursor (A and B in turn), right click removes
+/// cursors and middle click returns to the whole window following the present.
+pub struct ChartNavigation {
+    zoom: f64,                    // Shown part of the time window, 1.0 for all of it
+    end: Option<f64>,             // Time at the right edge when panned, the present when not
+    cursors: [Option<f64>; 2],    // Times of the measurement cursors
+    next_cursor: usize,           // Cursor placed by the next click
+    drag: Option<(Vector2, f64)>, // Mouse position where the button was pressed and `end` then
+    dragged: bool,
+}
+
+impl ChartNavigation {
+    pub fn new() -> Self {
+        Self {
+            zoom: 1.0,
+            end: None,
+            cursors: [None, None],
+            next_cursor: 0,
+            drag: None,
+            dragged: false,
+        }
+    }
+
+    fn window_sec(view: &ChartView, history: &History, now: f64) -> f64 {
+        let window = match view.window {
+            ChartWindow::Last(duration) => duration.as_secs_f64(),
+            ChartWindow::All => now - history.first_time().unwrap_or(now),
+        };
+        window.max(MIN_SHOWN_SEC)
+    }
+
+    /// Time range `(start, end)` shown by the chart.
+    fn time_range(&self, view: &ChartView, history: &History, now: f64) -> (f64, f64) {
+        let end = self.end.unwrap_or(now);
+        (
+            end - ChartNavigation::window_sec(view, history, now) * self.zoom,
+            end,
+        )
+    }
+
+    /// Handles the mouse over the chart `area`; `now` is the present time of the chart.
+    pub fn update(
+        &mut self,
+        rl: &RaylibHandle,
+        area: Rectangle,
+        view: &ChartView,
+        history: &History,
+        now: f64,
+    ) {
+        let mouse = rl.get_mouse_position();
+        let over = area.check_collision_point_rec(mouse);
+        let (start, end) = self.time_range(view, history, now);
+        let seconds_per_px = (end - start) / area.width as f64;
+        let time_at = |x: f32| start + (x - area.x) as f64 * seconds_per_px;
+
+        let wheel = rl.get_mouse_wheel_move();
+        if over && wheel != 0.0 {
+            let window = ChartNavigation::window_sec(view, history, now);
+            let zoom =
+                (self.zoom * ZOOM_STEP.powf(-wheel as f64)).clamp(MIN_SHOWN_SEC / window, 1.0);
+            // A panned chart keeps the time under the mouse in place, a live one the present
+            if let Some(end) = self.end.as_mut() {
+                let anchor = time_at(mouse.x);
+                *end = (anchor + (*end - anchor) * zoom / self.zoom).min(now);
+            }
+            self.zoom = zoom;
+        }
+
+        if over && rl.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) {
+            self.drag = Some((mouse, end));
+            self.dragged = false;
+        }
+        if let Some((grab, grab_end)) = self.drag {
+            let moved = mouse - grab;
+            self.dragged |= moved.x.abs() > CLICK_MAX_MOVE || moved.y.abs() > CLICK_MAX_MOVE;
+            if self.dragged {
+                let panned_end = grab_end - moved.x as f64 * seconds_per_px;
+                // Dragged up to the present, the chart follows it again
+                self.end = Some(panned_end).filter(|end| *end < now);
+            }
+            if !rl.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
+                if !self.dragged {
+                    self.cursors[self.next_cursor] = Some(time_at(grab.x));
+                    self.next_cursor = (self.next_cursor + 1) % self.cursors.len();
+                }
+                self.drag = None;
+            }
+        }
+
+        if over && rl.is_mouse_button_pressed(MouseButton::MOUSE_RIGHT_BUTTON) {
+            self.cursors = [None, None];
+            self.next_cursor = 0;
+        }
+        if over && rl.is_mouse_button_pressed(MouseButton::MOUSE_MIDDLE_BUTTON) {
+            self.zoom = 1.0;
+            self.end = None;
+        }
+    }
+}
+
+/// Value at the time, interpolated between the nearest points (the first or last value
+/// outside of them).
+fn value_at(points: &[ChartPoint], time: f64) -> Option<f64> {
+    let i = points.partition_point(|point| point.time < time);
+    match (i.checked_sub(1).map(|i| &points[i]), points.get(i)) {
+        (Some(before), Some(after)) if after.time > before.time => Some(
+            before.value
+                + (after.value - before.value) * (time - before.time) / (after.time - before.time),
+        ),
+        (Some(before), _) => Some(before.value),
+        (None, after) => after.map(|after| after.value),
+    }
+}
+
+/// Value with significant digits, without an SI prefix (values are in the unit of the channel).
+fn format_readout(value: f64) -> String {
+    format_engineering(value, READOUT_SIGNIFICANT_DIGITS, Some(SiPrefix::None)).0
+}
+
+/// Time with unit, i.e. `10s`, `1m`, `10m` or `2h`.
+pub fn parse_time(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (number, unit_seconds) = if let Some(number) = text.strip_suffix('s') {
+        (number, 1)
+    } else if let Some(number) = text.strip_suffix('m') {
+        (number, 60)
+    } else if let Some(number) = text.strip_suffix('h') {
+        (number, 3600)
+    } else {
+        return None;
+    };
+    match number.parse::<u64>() {
+        Ok(number) if number > 0 => Some(Duration::from_secs(number * unit_seconds)),
+        _ => None,
+    }
+}
+
+/// Round distance of ticks (1, 2 or 5 times a power of ten), giving at most `max_ticks`
+/// ticks over the span.
+fn tick_step(span: f64, max_ticks: f64) -> f64 {
+    let min_step = span / max_ticks.max(1.0);
+    if !(min_step.is_finite() && min_step > 0.0) {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(min_step.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|multiple| multiple * magnitude)
+        .find(|step| *step >= min_step)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Average of point values in the `period` up to each point, as `(time, average)`.
+fn moving_average(points: &[ChartPoint], period: f64) -> Vec<(f64, f64)> {
+    let mut sum = 0.0;
+    let mut from = 0;
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            sum += point.value;
+            while points[from].time <= point.time - period {
+                sum -= points[from].value;
+                from += 1;
+            }
+            (point.time, sum / (i + 1 - from) as f64)
+        })
+        .collect()
+}
+
+/// Filled triangle with vertices in any order (raylib draws only counter-clockwise ones).
+fn draw_triangle_any(d: &mut impl RaylibDraw, a: Vector2, b: Vector2, c: Vector2, color: Color) {
+    if (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x) < 0.0 {
+        d.draw_triangle(a, b, c, color);
+    } else {
+        d.draw_triangle(a, c, b, color);
+    }
+}
+
+/// Filled quadrilateral with vertices in order around it.
+fn draw_quad(d: &mut impl RaylibDraw, vertices: [Vector2; 4], color: Color) {
+    let [a, b, c, e] = vertices;
+    draw_triangle_any(d, a, b, c, color);
+    draw_triangle_any(d, a, c, e, color);
+}
+
+/// Time in the largest whole unit, i.e. 30s, 5m or 2h (90s stays in seconds).
+fn format_time(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    if seconds >= 3600 && seconds.is_multiple_of(3600) {
+        format!("{}h", seconds / 3600)
+    } else if seconds >= 60 && seconds.is_multiple_of(60) {
+        format!("{}m", seconds / 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Draws readings in the time window ending at `now` (seconds since the UNIX epoch), zoomed
+/// and panned by the navigation, in the style of the view, with the time axis labelled by age
+/// of the readings and the value axis by values. In the points style decimated readings are
+/// bars from the lowest to the highest reading. Measurement cursors are read out at the top.
+pub fn draw_chart(
+    d: &mut RaylibDrawHandle<'_>,
+    layout: &ChartLayout<'_>,
+    y_label: &str,
+    history: &History,
+    view: &ChartView,
+    navigation: &ChartNavigation,
+    now: f64,
+) {
+    let ChartLayout {
+        area,
+        point_circle_size,
+        grid_step,
+        point_color,
+        theme,
+    } = *layout;
+    let x_axis_y = area.y + area.height;
+    let y_axis_x = area.x;
+    let tick_font_size = (grid_step / 4.0) as i32;
+
+    let (start, end) = navigation.time_range(view, history, now);
+    let span = end - start;
+    // The moving average needs readings from before the window for its first points
+    let lead = view.average.map_or(0.0, |period| period.as_secs_f64());
+    let points = history.points(start - lead, end, MAX_POINTS);
+    let average = view
+        .average
+        .map(|period| moving_average(&points, period.as_secs_f64()));
+    let points = &points[points.partition_point(|point| point.time < start)..];
+    let values: Vec<f64> = points
+        .iter()
+        .flat_map(|point| [point.min, point.max])
+        .collect();
+    let (min_y, max_y) = view.range.of(&values);
+    let scale_x = area.width as f64 / span;
+    let scale_y = area.height as f64 / (max_y - min_y);
+    let to_x = |time: f64| area.x + ((time - start) * scale_x) as f32;
+    let to_y = |value: f64| x_axis_y - ((value - min_y) * scale_y) as f32;
+
+    // Value ticks at round values, with as many decimals as the tick distance needs
+    let value_step = tick_step(max_y - min_y, (area.height / grid_step) as f64);
+    let decimals = (-value_step.log10().floor()).max(0.0) as usize;
+    let first_tick = (min_y / value_step).ceil();
+    for i in 0..MAX_VALUE_TICKS {
+        let value = (first_tick + i as f64) * value_step;
+        if value > max_y {
+            break;
+        }
+        let y = to_y(value);
+        d.draw_line(
+            y_axis_x as i32,
+            y as i32,
+            (y_axis_x + area.width) as i32,
+            y as i32,
+            theme.grid,
+        );
+        // Avoid -0.000 for a tick at zero
+        let label = format!("{:.*}", decimals, value + 0.0);
+        d.draw_text(
+            &label,
+            y_axis_x as i32 - measure_text(&label, tick_font_size) - tick_font_size / 2,
+            y as i32 - tick_font_size / 2,
+            tick_font_size,
+            theme.chart_labels,
+        );
+    }
+
+    // Time ticks at round ages of readings, the present is at the right edge unless panned
+    let time_step = TIME_TICK_STEPS_SEC
+        .iter()
+        .copied()
+        .find(|step| (step * scale_x) as f32 >= grid_step * TIME_TICK_MIN_SPACING)
+        .unwrap_or(TIME_TICK_STEPS_SEC[TIME_TICK_STEPS_SEC.len() - 1]);
+    let first_tick = ((now - end) / time_step).ceil() as i64;
+    let last_tick = ((now - start) / time_step).floor() as i64;
+    for i in first_tick..=last_tick {
+        let age = i as f64 * time_step;
+        let x = to_x(now - age);
+        d.draw_line(
+            x as i32,
+            x_axis_y as i32,
+            x as i32,
+            area.y as i32,
+            theme.grid,
+        );
+        let label = match i {
+            0 => "now".to_string(),
+            _ => format!("-{}", format_time(age)),
+        };
+        d.draw_text(
+            &label,
+            x as i32 - measure_text(&label, tick_font_size) / 2,
+            (x_axis_y + tick_font_size as f32 / 2.0) as i32,
+            tick_font_size,
+            theme.chart_labels,
+        );
+    }
+
+    d.draw_line(
+        y_axis_x as i32,
+        x_axis_y as i32,
+        (y_axis_x + area.width) as i32,
+        x_axis_y as i32,
+        theme.axes,
+    );
+    d.draw_line(
+        y_axis_x as i32,
+        x_axis_y as i32,
+        y_axis_x as i32,
+        area.y as i32,
+        theme.axes,
+    );
+    let mut time_label = format!("T: {}", view.window.name());
+    if navigation.zoom < 1.0 {
+        time_label += &format!("  ZOOM: {}", format_time(span));
+    }
+    if navigation.end.is_some() {
+        time_label += &format!("  PAN: -{}", format_time(now - end));
+    }
+    if let Some(period) = view.average {
+        time_label += &format!("  AVG: {}", format_time(period.as_secs_f64()));
+    }
+    d.draw_text(
+        &time_label,
+        (area.x + area.width / 2.0) as i32,
+        (x_axis_y + grid_step / 2.0) as i32,
+        LABEL_FONT_SIZE,
+        theme.chart_labels,
+    );
+    d.draw_text(
+        y_label,
+        (y_axis_x + tick_font_size as f32 / 2.0) as i32,
+        area.y as i32,
+        LABEL_FONT_SIZE,
+        theme.chart_labels,
+    );
+
+    // Values outside a fixed range are clipped by the chart area
+    let mut d = d.begin_scissor_mode(
+        area.x as i32,
+        area.y as i32,
+        area.width as i32,
+        area.height as i32,
+    );
+    let base_y = to_y(0.0).clamp(area.y, x_axis_y);
+    match view.style {
+        ChartStyle::Points => {
+            for point in points {
+                let x = to_x(point.time);
+                if point.max > point.min {
+                    d.draw_line_ex(
+                        Vector2::new(x, to_y(point.max)),
+                        Vector2::new(x, to_y(point.min)),
+                        point_circle_size * 2.0,
+                        point_color(point.value),
+                    );
+                } else {
+                    d.draw_circle(
+                        x as i32,
+                        to_y(point.value) as i32,
+                        point_circle_size,
+                        point_color(point.value),
+                    );
+                }
+            }
+        }
+        style => {
+            for pair in points.windows(2) {
+                let (previous, point) = (&pair[0], &pair[1]);
+                let color = point_color(point.value);
+                let from = Vector2::new(to_x(previous.time), to_y(previous.value));
+                let to = Vector2::new(to_x(point.time), to_y(point.value));
+                match style {
+                    ChartStyle::Step => {
+                        let corner = Vector2::new(to.x, from.y);
+                        d.draw_line_ex(from, corner, point_circle_size, color);
+                        d.draw_line_ex(corner, to, point_circle_size, color);
+                        continue;
+                    }
+                    ChartStyle::Area => draw_quad(
+                        &mut d,
+                        [
+                            from,
+                            to,
+                            Vector2::new(to.x, base_y),
+                            Vector2::new(from.x, base_y),
+                        ],
+                        color.fade(FILL_ALPHA),
+                    ),
+                    ChartStyle::Envelope => draw_quad(
+                        &mut d,
+                        [
+                            Vector2::new(from.x, to_y(previous.max)),
+                            Vector2::new(to.x, to_y(point.max)),
+                            Vector2::new(to.x, to_y(point.min)),
+                            Vector2::new(from.x, to_y(previous.min)),
+                        ],
+                        color.fade(FILL_ALPHA),
+                    ),
+                    _ => {}
+                }
+                d.draw_line_ex(from, to, point_circle_size, color);
+            }
+        }
+    }
+
+    if let Some(average) = average {
+        let average = &average[average.partition_point(|(time, _)| *time < start)..];
+        for pair in average.windows(2) {
+            d.draw_line_ex(
+                Vector2::new(to_x(pair[0].0), to_y(pair[0].1)),
+                Vector2::new(to_x(pair[1].0), to_y(pair[1].1)),
+                point_circle_size,
+                theme.chart_labels,
+            );
+        }
+    }
+
+    // Cursors with the value at them, differences when both are placed (a cursor outside of
+    // the zoomed or panned time range is read from the readings around it at the same
+    // resolution, only its position is not shown)
+    let mut readout = vec![];
+    let mut cursor_values = vec![];
+    for (name, time) in CURSOR_NAMES.iter().zip(navigation.cursors) {
+        let time = match time {
+            Some(time) => time,
+            None => continue,
+        };
+        let on_screen = (start..=end).contains(&time);
+        let value = if on_screen {
+            value_at(points, time)
+        } else {
+            value_at(&history.points(time - span, time + span, MAX_POINTS), time)
+        };
+        let value_text = value.map_or("---".to_string(), format_readout);
+        if on_screen {
+            let x = to_x(time);
+            d.draw_line_ex(
+                Vector2::new(x, area.y),
+                Vector2::new(x, x_axis_y),
+                point_circle_size / 2.0,
+                theme.axes,
+            );
+            d.draw_text(
+                name,
+                x as i32 + tick_font_size / 4,
+                x_axis_y as i32 - tick_font_size,
+                tick_font_size,
+                theme.axes,
+            );
+            readout.push(format!("{}: -{:.1}s {}", name, now - time, value_text));
+        } else {
+            readout.push(format!("{}: off-screen {}", name, value_text));
+        }
+        cursor_values.push((time, value));
+    }
+    if let [(time_a, Some(value_a)), (time_b, Some(value_b))] = cursor_values[..] {
+        let (delta_time, delta_value) = (time_b - time_a, value_b - value_a);
+        readout.push(format!("dT: {:.3}s", delta_time));
+        readout.push(format!("dV: {} {}", format_readout(delta_value), y_label));
+        if delta_time != 0.0 {
+            readout.push(format!(
+                "dV/dT: {} {}/s",
+                format_readout(delta_value / delta_time),
+                y_label
+            ));
+        }
+    }
+    if !readout.is_empty() {
+        let readout = readout.join("  ");
+        d.draw_text(
+            &readout,
+            (area.x + area.width) as i32 - measure_text(&readout, tick_font_size) - tick_font_size,
+            area.y as i32 + tick_font_size / 2,
+            tick_font_size,
+            theme.chart_labels,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_range() {
+        assert_eq!(
+            ChartRange::parse("auto"),
+            Ok(ChartRange::Auto {
+                zero_centered: false
+            })
+        );
+        assert_eq!(
+            ChartRange::parse(" auto_zero "),
+            Ok(ChartRange::Auto {
+                zero_centered: true
+            })
+        );
+        assert_eq!(
+            ChartRange::parse("4.9, 5.1"),
+            Ok(ChartRange::Fixed { min: 4.9, max: 5.1 })
+        );
+        assert_eq!(
+            ChartRange::parse("-1e-6,1e-6"),
+            Ok(ChartRange::Fixed {
+                min: -1e-6,
+                max: 1e-6
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_empty_or_too_narrow_range() {
+        for text in [
+            "",
+            "5",
+            "a,b",
+            "5,5",
+            "5.1,4.9",
+            "1,1.0000000001",
+            "nan,1",
+            "0,inf",
+            "-1e308,1e308",
+        ] {
+            assert!(ChartRange::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn pads_auto_range() {
+        let auto = ChartRange::Auto {
+            zero_centered: false,
+        };
+        let (min, max) = auto.of(&[1.0, 3.0, f64::INFINITY]);
+        assert!((min - 0.8).abs() < 1e-12 && (max - 3.2).abs() < 1e-12);
+        let (min, max) = auto.of(&[5.0, 5.0]);
+        assert!(min < 5.0 && max > 5.0 && is_usable_range(min, max));
+        assert_eq!(auto.of(&[0.0]), (-1.0, 1.0));
+        assert_eq!(auto.of(&[]), (-0.1, 1.1));
+        let (min, max) = auto.of(&[1.0, 1.0 + f64::EPSILON]);
+        assert!(is_usable_range(min, max));
+        let (min, max) = ChartRange::Auto {
+            zero_centered: true,
+        }
+        .of(&[-1.0, 3.0]);
+        assert!(min == -max && (max - 3.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn finds_round_tick_step() {
+        assert_eq!(tick_step(10.0, 10.0), 1.0);
+        assert_eq!(tick_step(10.0, 6.0), 2.0);
+        assert_eq!(tick_step(10.0, 3.0), 5.0);
+        assert_eq!(tick_step(0.2, 10.0), 0.02);
+        assert_eq!(tick_step(700.0, 1.0), 1000.0);
+        assert_eq!(tick_step(10.0, 0.0), 10.0);
+        assert_eq!(tick_step(0.0, 10.0), 1.0);
+        assert_eq!(tick_step(f64::NAN, 10.0), 1.0);
+    }
+}
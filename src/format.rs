@@ -0,0 +1,265 @@
+use crate::measurement::{Measurement, ReadingState, SiPrefix, Unit};
+
+const RAW_DISPLAY_CHARS: usize = 9;
+
+/// How a measurement value is formatted for the 7-segment display.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayFormat {
+    /// Value as reported by the meter, cut to the display width.
+    Raw,
+    /// Engineering notation with SI prefix moved into the unit (i.e. `123.00 µV`).
+    Engineering {
+        significant_digits: usize,
+        locked_prefix: Option<SiPrefix>, // Fixed range, to avoid prefix flicker
+    },
+}
+
+/// Display resolution of a real meter given as counts, i.e. 3½ digits = 2000 counts (max. `1999`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Resolution {
+    pub counts: u64,
+}
+
+impl Resolution {
+    /// Accepts digits (`6`), half digits (`4.5`, `4½`) or counts (`6000`, `50000`).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let invalid = || format!("Invalid display resolution '{}'", text);
+        let (digits, half) =
+            if let Some(digits) = text.strip_suffix(".5").or_else(|| text.strip_suffix('½')) {
+                (digits, true)
+            } else {
+                (text, false)
+            };
+        let number = digits.parse::<u64>().map_err(|_| invalid())?;
+        let counts = match (number, half) {
+            (1..=9, false) => 10u64.pow(number as u32),
+            (1..=8, true) => 2 * 10u64.pow(number as u32),
+            (10.., false) => number,
+            _ => return Err(invalid()),
+        };
+        Ok(Resolution { counts })
+    }
+
+    /// Number of digit positions on the display.
+    fn digit_positions(&self) -> usize {
+        (self.counts - 1).to_string().len()
+    }
+
+    /// Rounds the value to the display resolution, with the decimal point placed like in the
+    /// lowest range which can show the value, and leading blanking to the full display width.
+    /// Returns `None` when the value exceeds the display counts.
+    pub fn format(&self, value: f64) -> Option<String> {
+        let positions = self.digit_positions();
+        for decimals in (0..positions).rev() {
+            let counts = (value.abs() * 10f64.powi(decimals as i32)).round();
+            if counts < self.counts as f64 {
+                let rounded = value.signum() * counts / 10f64.powi(decimals as i32);
+                let text = format!("{:.*}", decimals, rounded);
+                // Sign position, all digits and the decimal point
+                let width = 1 + positions + usize::from(decimals > 0);
+                return Some(format!("{:>width$}", text, width = width));
+            }
+        }
+        None
+    }
+}
+
+/// Formats a measurement for the display, returning the value text and the unit to show next to it.
+/// When `resolution` is given, it replaces raw cut or significant digits of the format.
+pub fn format_measurement(
+    measurement: &Measurement,
+    format: &DisplayFormat,
+    resolution: Option<Resolution>,
+) -> (String, Unit) {
+    if !measurement.is_valid() {
+        return (
+            measurement.state.display_text().to_string(),
+            measurement.unit.clone(),
+        );
+    }
+    match (format, resolution) {
+        (DisplayFormat::Raw, None) => {
+            let mut text = format!("{:.8}", measurement.value);
+            text.truncate(RAW_DISPLAY_CHARS);
+            (text, measurement.unit.clone())
+        }
+        (DisplayFormat::Raw, Some(resolution)) => {
+            let text = resolution
+                .format(measurement.value)
+                .unwrap_or_else(|| overload_text(measurement.value));
+            (text, measurement.unit.clone())
+        }
+        (
+            DisplayFormat::Engineering {
+                significant_digits,
+                locked_prefix,
+            },
+            None,
+        ) => {
+            let (text, prefix) =
+                format_engineering(measurement.si_value(), *significant_digits, *locked_prefix);
+            let unit = Unit {
+                prefix,
+                ..measurement.unit.clone()
+            };
+            (text, unit)
+        }
+        (DisplayFormat::Engineering { locked_prefix, .. }, Some(resolution)) => {
+            let si_value = measurement.si_value();
+            let mut prefix = locked_prefix.unwrap_or_else(|| auto_prefix(si_value));
+            let text = loop {
+                match resolution.format(si_value / prefix.factor()) {
+                    Some(text) => break text,
+                    None => match next_prefix(prefix).filter(|_| locked_prefix.is_none()) {
+                        Some(next) => prefix = next,
+                        None => break overload_text(si_value),
+                    },
+                }
+            };
+            let unit = Unit {
+                prefix,
+                ..measurement.unit.clone()
+            };
+            (text, unit)
+        }
+    }
+}
+
+fn overload_text(value: f64) -> String {
+    let state = if value < 0.0 {
+        ReadingState::OverloadNegative
+    } else {
+        ReadingState::OverloadPositive
+    };
+    state.display_text().to_string()
+}
+
+/// Formats a value given in base SI unit with `significant_digits` digits, choosing the SI prefix
+/// which keeps 1 to 3 digits before the decimal point (or using `locked_prefix` when given).
+pub fn format_engineering(
+    si_value: f64,
+    significant_digits: usize,
+    locked_prefix: Option<SiPrefix>,
+) -> (String, SiPrefix) {
+    let significant_digits = significant_digits.max(1);
+    let mut prefix = locked_prefix.unwrap_or_else(|| auto_prefix(si_value));
+    loop {
+        let scaled = si_value / prefix.factor();
+        let integer_digits = integer_digits(scaled);
+        let decimals = significant_digits.saturating_sub(integer_digits);
+        let text = format!("{:.*}", decimals, scaled);
+        // Rounding may carry over to the next range (i.e. 999.996 -> 1000.00)
+        if locked_prefix.is_none() && integer_digits < 4 && integer_digits_of(&text) > 3 {
+            if let Some(next) = next_prefix(prefix) {
+                prefix = next;
+                continue;
+            }
+        }
+        return (text, prefix);
+    }
+}
+
+fn auto_prefix(si_value: f64) -> SiPrefix {
+    if si_value == 0.0 || !si_value.is_finite() {
+        return SiPrefix::None;
+    }
+    let exponent = si_value.abs().log10().floor() as i32;
+    let exponent = exponent.div_euclid(3) * 3;
+    SiPrefix::ALL
+        .iter()
+        .copied()
+        .find(|prefix| prefix.exponent() == exponent)
+        .unwrap_or(if exponent < 0 {
+            SiPrefix::Pico
+        } else {
+            SiPrefix::Giga
+        })
+}
+
+fn next_prefix(prefix: SiPrefix) -> Option<SiPrefix> {
+    let position = SiPrefix::ALL.iter().position(|p| *p == prefix)?;
+    SiPrefix::ALL.get(position + 1).copied()
+}
+
+fn integer_digits(value: f64) -> usize {
+    let value = value.abs();
+    if value < 1.0 {
+        1
+    } else {
+        value.log10().floor() as usize + 1
+    }
+}
+
+fn integer_digits_of(text: &str) -> usize {
+    text.trim_start_matches('-')
+        .split('.')
+        .next()
+        .map_or(0, |integer| integer.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_parses_digits_and_counts() {
+        assert_eq!(Resolution::parse("3.5"), Ok(Resolution { counts: 2000 }));
+        assert_eq!(Resolution::parse("3½"), Ok(Resolution { counts: 2000 }));
+        assert_eq!(Resolution::parse("6"), Ok(Resolution { counts: 1000000 }));
+        assert_eq!(Resolution::parse(" 6000 "), Ok(Resolution { counts: 6000 }));
+        assert!(Resolution::parse("0").is_err());
+        assert!(Resolution::parse("9.5").is_err());
+        assert!(Resolution::parse("abc").is_err());
+    }
+
+    #[test]
+    fn resolution_rounds_to_lowest_range() {
+        let resolution = Resolution { counts: 2000 };
+        assert_eq!(resolution.format(1.2344), Some(" 1.234".to_string()));
+        assert_eq!(resolution.format(12.346), Some(" 12.35".to_string()));
+        assert_eq!(resolution.format(-0.5), Some("-0.500".to_string()));
+        assert_eq!(resolution.format(1999.0), Some(" 1999".to_string()));
+        assert_eq!(resolution.format(2500.0), None);
+    }
+
+    #[test]
+    fn engineering_chooses_prefix() {
+        assert_eq!(
+            format_engineering(0.000123, 5, None),
+            ("123.00".to_string(), SiPrefix::Micro)
+        );
+        assert_eq!(
+            format_engineering(1234.0, 4, None),
+            ("1.234".to_string(), SiPrefix::Kilo)
+        );
+        assert_eq!(
+            format_engineering(-0.0025, 3, None),
+            ("-2.50".to_string(), SiPrefix::Milli)
+        );
+        assert_eq!(
+            format_engineering(0.0, 5, None),
+            ("0.0000".to_string(), SiPrefix::None)
+        );
+    }
+
+    #[test]
+    fn engineering_carries_rounding_to_next_prefix() {
+        assert_eq!(
+            format_engineering(999.996, 5, None),
+            ("1.0000".to_string(), SiPrefix::Kilo)
+        );
+    }
+
+    #[test]
+    fn engineering_keeps_locked_prefix() {
+        assert_eq!(
+            format_engineering(0.5, 4, Some(SiPrefix::Milli)),
+            ("500.0".to_string(), SiPrefix::Milli)
+        );
+        assert_eq!(
+            format_engineering(1.5, 4, Some(SiPrefix::None)),
+            ("1.500".to_string(), SiPrefix::None)
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use crate::bargraph::BarScale;
+use crate::measurement::Unit;
+use raylib::prelude::*;
+
+// Meter face for the default window size, the pivot is below the panel like on wide panel meters
+const FACE_WIDTH: f32 = 900.0;
+const SCALE_RADIUS: f32 = 420.0;
+const SCALE_TOP_Y: f32 = 45.0;
+const SCALE_HALF_ANGLE_DEG: f32 = 35.0;
+const MAJOR_DIVISIONS: usize = 10;
+const MINOR_PER_MAJOR: usize = 5;
+const MAJOR_TICK_LENGTH: f32 = 16.0;
+const MINOR_TICK_LENGTH: f32 = 8.0;
+const LABEL_FONT_SIZE: f32 = 14.0;
+const UNIT_FONT_SIZE: f32 = 28.0;
+const NEEDLE_THICKNESS: f32 = 2.5;
+const MAX_LABEL_DECIMALS: usize = 6;
+
+// Ballistics of a moving-coil movement: a damped spring, slightly underdamped
+const NATURAL_FREQUENCY_HZ: f32 = 1.5;
+const DAMPING_RATIO: f32 = 0.6;
+const MAX_STEP_SEC: f32 = 0.002; // Integration step, stable for any frame rate
+
+/// Decimals which show every significant digit of the step between labels (i.e. 2 for 0.25).
+fn label_decimals(step: f64) -> usize {
+    (0..MAX_LABEL_DECIMALS)
+        .find(|decimals| {
+            let scaled = step.abs() * 10f64.powi(*decimals as i32);
+            (scaled - scaled.round()).abs() < 1e-6 * scaled.max(1.0)
+        })
+        .unwrap_or(MAX_LABEL_DECIMALS)
+}
+
+/// Analog moving-coil meter face with a needle, an alternative to the segmented digits.
+pub struct Gauge {
+    scale: BarScale,
+    range: (f64, f64),
+    position: f32, // Needle position in the range, 0.0 (min) to 1.0 (max)
+    velocity: f32,
+}
+
+impl Gauge {
+    pub fn new(scale: BarScale) -> Self {
+        Self {
+            scale,
+            range: scale.initial_range(),
+            position: 0.0,
+            velocity: 0.0,
+        }
+    }
+
+    fn zero_position(&self) -> f32 {
+        let (min, max) = self.range;
+        ((0.0 - min) / (max - min)).clamp(0.0, 1.0) as f32
+    }
+
+    /// Moves the needle towards the value (overload as infinity); without a value it
+    /// returns to zero, like a meter which is not connected.
+    pub fn update(&mut self, value: Option<f64>, frame_time: f32) {
+        let target = match value {
+            Some(value) if !value.is_nan() => {
+                if let Some(range) = self.scale.range_for(value) {
+                    self.range = range;
+                }
+                let (min, max) = self.range;
+                ((value - min) / (max - min)).clamp(-0.05, 1.05) as f32
+            }
+            _ => self.zero_position(),
+        };
+
+        let omega = 2.0 * std::f32::consts::PI * NATURAL_FREQUENCY_HZ;
+        let mut remaining = frame_time;
+        while remaining > 0.0 {
+            let step = remaining.min(MAX_STEP_SEC);
+            let acceleration = omega * omega * (target - self.position)
+                - 2.0 * DAMPING_RATIO * omega * self.velocity;
+            self.velocity += acceleration * step;
+            self.position += self.velocity * step;
+            // Mechanical end stops
+            if !(0.0..=1.0).contains(&self.position) {
+                self.position = self.position.clamp(0.0, 1.0);
+                self.velocity = 0.0;
+            }
+            remaining -= step;
+        }
+    }
+
+    /// Draws the face with scale markings and the needle, scaled from the default window size
+    /// into `area`.
+    pub fn draw(
+        &self,
+        d: &mut RaylibDrawHandle<'_>,
+        area: Rectangle,
+        scale: f32,
+        unit: &Unit,
+        needle_color: Color,
+        marking_color: Color,
+    ) {
+        let px = |base: f32| base * scale;
+        let pivot = Vector2::new(
+            area.x + px(FACE_WIDTH / 2.0),
+            area.y + px(SCALE_TOP_Y + SCALE_RADIUS),
+        );
+        let point = |position: f32, radius: f32| {
+            let angle = ((position * 2.0 - 1.0) * SCALE_HALF_ANGLE_DEG).to_radians();
+            Vector2::new(
+                pivot.x + px(radius) * angle.sin(),
+                pivot.y - px(radius) * angle.cos(),
+            )
+        };
+
+        let mut d = d.begin_scissor_mode(
+            area.x as i32,
+            area.y as i32,
+            area.width as i32,
+            area.height as i32,
+        );
+
+        // Scale arc with ticks and labels
+        let divisions = MAJOR_DIVISIONS * MINOR_PER_MAJOR;
+        for i in 0..divisions {
+            let (from, to) = (
+                i as f32 / divisions as f32,
+                (i + 1) as f32 / divisions as f32,
+            );
+            d.draw_line_ex(
+                point(from, SCALE_RADIUS),
+                point(to, SCALE_RADIUS),
+                px(1.5),
+                marking_color,
+            );
+        }
+        let (min, max) = self.range;
+        let step = (max - min) / MAJOR_DIVISIONS as f64;
+        let decimals = label_decimals(step);
+        for i in 0..=divisions {
+            let position = i as f32 / divisions as f32;
+            let major = i % MINOR_PER_MAJOR == 0;
+            let length = if major {
+                MAJOR_TICK_LENGTH
+            } else {
+                MINOR_TICK_LENGTH
+            };
+            d.draw_line_ex(
+                point(position, SCALE_RADIUS),
+                point(position, SCALE_RADIUS - length),
+                px(if major { 2.0 } else { 1.0 }),
+                marking_color,
+            );
+            if major {
+                let value = min + step * (i / MINOR_PER_MAJOR) as f64;
+                let label = format!("{:.*}", decimals, value);
+                let font_size = px(LABEL_FONT_SIZE) as i32;
+                let pos = point(position, SCALE_RADIUS - MAJOR_TICK_LENGTH - LABEL_FONT_SIZE);
+                d.draw_text(
+                    &label,
+                    pos.x as i32 - measure_text(&label, font_size) / 2,
+                    pos.y as i32 - font_size / 2,
+                    font_size,
+                    marking_color,
+                );
+            }
+        }
+
+        let unit = unit.to_string();
+        let font_size = px(UNIT_FONT_SIZE) as i32;
+        let unit_pos = point(
+            0.5,
+            SCALE_RADIUS - MAJOR_TICK_LENGTH - LABEL_FONT_SIZE * 3.0,
+        );
+        d.draw_text(
+            &unit,
+            unit_pos.x as i32 - measure_text(&unit, font_size) / 2,
+            unit_pos.y as i32,
+            font_size,
+            marking_color,
+        );
+
+        // Needle from the (hidden) pivot over the scale
+        d.draw_line_ex(
+            pivot,
+            point(self.position, SCALE_RADIUS + MAJOR_TICK_LENGTH / 2.0),
+            px(NEEDLE_THICKNESS),
+            needle_color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_decimals_keep_significant_digits_of_step() {
+        assert_eq!(label_decimals(0.25), 2);
+        assert_eq!(label_decimals(0.1), 1);
+        assert_eq!(label_decimals(0.2), 1);
+        assert_eq!(label_decimals(1.0), 0);
+        assert_eq!(label_decimals(20.0), 0);
+        assert_eq!(label_decimals(0.001), 3);
+        assert_eq!(label_decimals(1.0 / 3.0), MAX_LABEL_DECIMALS);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+
+const RAW_CAPACITY: usize = 20000; // Latest readings kept as they are
+const LEVEL_CAPACITY: usize = 4096; // Buckets kept by each level
+const LEVEL_BUCKET_SEC: [f64; 5] = [1.0, 10.0, 60.0, 600.0, 3600.0]; // From the finest level
+
+/// Point of the chart: a single reading or a bucket of readings with their average and
+/// envelope, placed at the time of the first reading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartPoint {
+    pub time: f64, // Seconds since the UNIX epoch
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ChartPoint {
+    fn reading(time: f64, value: f64) -> Self {
+        Self {
+            time,
+            value,
+            min: value,
+            max: value,
+        }
+    }
+}
+
+/// Readings of a fixed time resolution, the oldest buckets are dropped when it is full.
+struct Level {
+    bucket_sec: f64,
+    buckets: VecDeque<(ChartPoint, u32)>, // Point and the number of readings in it
+    complete: bool,                       // No bucket has been dropped
+}
+
+impl Level {
+    fn new(bucket_sec: f64) -> Self {
+        Self {
+            bucket_sec,
+            buckets: VecDeque::new(),
+            complete: true,
+        }
+    }
+
+    fn add(&mut self, time: f64, value: f64) {
+        let index = (time / self.bucket_sec).floor();
+        match self.buckets.back_mut() {
+            // Readings with the time going back (clock adjustment) stay in the last bucket
+            Some((point, count)) if index <= (point.time / self.bucket_sec).floor() => {
+                *count += 1;
+                point.value += (value - point.value) / *count as f64;
+                point.min = point.min.min(value);
+                point.max = point.max.max(value);
+            }
+            _ => {
+                self.buckets
+                    .push_back((ChartPoint::reading(time, value), 1));
+                if self.buckets.len() > LEVEL_CAPACITY {
+                    self.buckets.pop_front();
+                    self.complete = false;
+                }
+            }
+        }
+    }
+}
+
+/// Readings of a channel for the chart with bounded memory: the latest readings in a ring
+/// buffer and the whole session decimated to min/max buckets of several resolutions, so a
+/// chart of any time window gets a limited number of points keeping the envelope of readings.
+pub struct History {
+    raw: VecDeque<(f64, f64)>, // Time and value
+    raw_complete: bool,        // No reading has been dropped
+    levels: Vec<Level>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self {
+            raw: VecDeque::new(),
+            raw_complete: true,
+            levels: LEVEL_BUCKET_SEC
+                .iter()
+                .map(|sec| Level::new(*sec))
+                .collect(),
+        }
+    }
+
+    /// Adds a reading, readings are expected in time order.
+    pub fn add(&mut self, time: f64, value: f64) {
+        self.raw.push_back((time, value));
+        if self.raw.len() > RAW_CAPACITY {
+            self.raw.pop_front();
+            self.raw_complete = false;
+        }
+        for level in &mut self.levels {
+            level.add(time, value);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = History::new();
+    }
+
+    /// Time of the oldest kept reading.
+    pub fn first_time(&self) -> Option<f64> {
+        let oldest_raw = self.raw.front().map(|(time, _)| *time);
+        let oldest_bucket = self
+            .levels
+            .last()
+            .and_then(|level| level.buckets.front())
+            .map(|(point, _)| point.time);
+        oldest_bucket.or(oldest_raw)
+    }
+
+    /// Points from `start` to `end` in the finest resolution giving at most `max_points` of
+    /// them (readings as they are when possible); only the coarsest level can give more, for
+    /// sessions longer than `max_points` of its buckets.
+    pub fn points(&self, start: f64, end: f64, max_points: usize) -> Vec<ChartPoint> {
+        let from = self.raw.partition_point(|(time, _)| *time < start);
+        let to = self.raw.partition_point(|(time, _)| *time <= end);
+        if (self.raw_complete || from > 0) && to - from <= max_points {
+            return self
+                .raw
+                .range(from..to)
+                .map(|(time, value)| ChartPoint::reading(*time, *value))
+                .collect();
+        }
+        for (i, level) in self.levels.iter().enumerate() {
+            let from = level
+                .buckets
+                .partition_point(|(point, _)| point.time < start);
+            let to = level
+                .buckets
+                .partition_point(|(point, _)| point.time <= end);
+            let covers_start = level.complete || from > 0;
+            if (covers_start && to - from <= max_points) || i == self.levels.len() - 1 {
+                return level
+                    .buckets
+                    .range(from..to)
+                    .map(|(point, _)| *point)
+                    .collect();
+            }
+        }
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(readings: impl Iterator<Item = (f64, f64)>) -> History {
+        let mut history = History::new();
+        for (time, value) in readings {
+            history.add(time, value);
+        }
+        history
+    }
+
+    #[test]
+    fn gives_readings_as_they_are() {
+        let history = history((0..10).map(|i| (100.0 + i as f64 * 0.5, i as f64)));
+        let points = history.points(101.0, 102.0, 100);
+        assert_eq!(
+            points,
+            vec![
+                ChartPoint::reading(101.0, 2.0),
+                ChartPoint::reading(101.5, 3.0),
+                ChartPoint::reading(102.0, 4.0),
+            ]
+        );
+        assert_eq!(history.first_time(), Some(100.0));
+    }
+
+    #[test]
+    fn decimates_to_envelope_above_max_points() {
+        let history = history((0..100).map(|i| (i as f64 * 0.1, i as f64)));
+        let points = history.points(0.0, 10.0, 50);
+        assert_eq!(points.len(), 10);
+        for (i, point) in points.iter().enumerate() {
+            let first = i as f64 * 10.0;
+            assert!((point.time - i as f64 * 1.0).abs() < 1e-9);
+            assert_eq!(point.min, first);
+            assert_eq!(point.max, first + 9.0);
+            assert!((point.value - (first + 4.5)).abs() < 1e-9);
+        }
+        // Coarser level when the finer one still gives too many points
+        let points = history.points(0.0, 10.0, 5);
+        assert_eq!(points.len(), 1);
+        assert_eq!((points[0].min, points[0].max), (0.0, 99.0));
+    }
+
+    #[test]
+    fn uses_raw_readings_only_when_they_cover_the_start() {
+        let history = history((0..=RAW_CAPACITY).map(|i| (i as f64, 1.0)));
+        // The first reading has been dropped, so raw readings no longer cover the session
+        let points = history.points(0.0, RAW_CAPACITY as f64, 2 * RAW_CAPACITY);
+        assert_eq!(points.len(), RAW_CAPACITY / 10 + 1);
+        assert!(points.iter().all(|point| point.time % 10.0 == 0.0));
+        // A window after the oldest kept reading
+        let points = history.points(100.0, 200.0, 1000);
+        assert_eq!(points.len(), 101);
+        assert_eq!(points[0], ChartPoint::reading(100.0, 1.0));
+    }
+
+    #[test]
+    fn keeps_readings_with_time_going_back_in_the_last_bucket() {
+        let history = history([(10.0, 1.0), (10.5, 2.0), (9.0, 3.0)].into_iter());
+        assert_eq!(history.points(0.0, 20.0, 10).len(), 3);
+        let points = history.points(0.0, 20.0, 2);
+        assert_eq!(
+            points,
+            vec![ChartPoint {
+                time: 10.0,
+                value: 2.0,
+                min: 1.0,
+                max: 3.0,
+            }]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::channel::{Channel, ChannelAction};
+use raylib::prelude::*;
+
+const BUTTON_BAR_HEIGHT_RATIO: f32 = 0.14;
+const BUTTON_BAR_MIN_HEIGHT: f32 = 72.0; // Comfortable for a finger on small touchscreens
+const BUTTON_BAR_OVERLAY_HEIGHT_RATIO: f32 = 0.1;
+const BUTTON_BAR_OVERLAY_MIN_HEIGHT: f32 = 28.0;
+const BUTTON_MARGIN_RATIO: f32 = 0.08;
+const BUTTON_COLOR: Color = Color::DARKGRAY;
+const BUTTON_ACTIVE_COLOR: Color = Color::GRAY;
+const BUTTON_TEXT_COLOR: Color = Color::WHITE;
+
+const BUTTONS: [(&str, ButtonAction); 7] = [
+    ("HOLD", ButtonAction::Channel(ChannelAction::Hold)),
+    ("REL", ButtonAction::Channel(ChannelAction::Rel)),
+    ("RESET", ButtonAction::Channel(ChannelAction::Reset)),
+    ("PAUSE", ButtonAction::Channel(ChannelAction::Pause)),
+    ("CHART", ButtonAction::Channel(ChannelAction::CycleChart)),
+    ("ACK", ButtonAction::Channel(ChannelAction::AckAlarm)),
+    ("SETUP", ButtonAction::Settings),
+];
+
+/// What a button does: an action on the selected channel or opening the settings panel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ButtonAction {
+    Channel(ChannelAction),
+    Settings,
+}
+
+/// Row of buttons along the bottom edge of the screen, acting on the selected channel:
+/// large touch buttons in kiosk mode, smaller ones over the channels in a window.
+pub struct ButtonBar {
+    area: Rectangle,
+}
+
+impl ButtonBar {
+    pub fn new(screen_width: i32, screen_height: i32) -> Self {
+        Self::with_height(
+            screen_width,
+            screen_height,
+            screen_height as f32 * BUTTON_BAR_HEIGHT_RATIO,
+            BUTTON_BAR_MIN_HEIGHT,
+        )
+    }
+
+    /// Buttons for the mouse, shown over the channels while the cursor is in the window.
+    pub fn overlay(screen_width: i32, screen_height: i32) -> Self {
+        Self::with_height(
+            screen_width,
+            screen_height,
+            screen_height as f32 * BUTTON_BAR_OVERLAY_HEIGHT_RATIO,
+            BUTTON_BAR_OVERLAY_MIN_HEIGHT,
+        )
+    }
+
+    fn with_height(screen_width: i32, screen_height: i32, height: f32, min_height: f32) -> Self {
+        let height = height.max(min_height).min(screen_height as f32 / 2.0);
+        Self {
+            area: Rectangle::new(
+                0.0,
+                screen_height as f32 - height,
+                screen_width as f32,
+                height,
+            ),
+        }
+    }
+
+    /// Height of the screen above the buttons, left for channels.
+    pub fn top(&self) -> f32 {
+        self.area.y
+    }
+
+    fn button(&self, index: usize) -> Rectangle {
+        let width = self.area.width / BUTTONS.len() as f32;
+        let margin = self.area.height * BUTTON_MARGIN_RATIO;
+        Rectangle::new(
+            self.area.x + index as f32 * width + margin,
+            self.area.y + margin,
+            width - 2.0 * margin,
+            self.area.height - 2.0 * margin,
+        )
+    }
+
+    /// Action of the button at the touched (or clicked) point.
+    pub fn action_at(&self, point: Vector2) -> Option<ButtonAction> {
+        (0..BUTTONS.len())
+            .find(|&i| self.button(i).check_collision_point_rec(point))
+            .map(|i| BUTTONS[i].1)
+    }
+
+    /// Draws the buttons; HOLD, REL, PAUSE and ACK are highlighted while active on the selected
+    /// channel.
+    pub fn draw(&self, d: &mut RaylibDrawHandle<'_>, channel: &Channel) {
+        for (i, (label, action)) in BUTTONS.iter().enumerate() {
+            let button = self.button(i);
+            let active = match action {
+                ButtonAction::Channel(ChannelAction::Hold) => channel.is_held(),
+                ButtonAction::Channel(ChannelAction::Rel) => channel.is_rel(),
+                ButtonAction::Channel(ChannelAction::Pause) => channel.is_paused(),
+                ButtonAction::Channel(ChannelAction::AckAlarm) => channel.alarm.is_latched(),
+                _ => false,
+            };
+            let color = if active {
+                BUTTON_ACTIVE_COLOR
+            } else {
+                BUTTON_COLOR
+            };
+            d.draw_rectangle_rounded(button, 0.2, 8, color);
+
+            let font_size = (button.height * 0.35) as i32;
+            let text_width = measure_text(label, font_size);
+            d.draw_text(
+                label,
+                (button.x + (button.width - text_width as f32) / 2.0) as i32,
+                (button.y + (button.height - font_size as f32) / 2.0) as i32,
+                font_size,
+                BUTTON_TEXT_COLOR,
+            );
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::measurement::Measurement;
+
+/// Position of a reading relative to the channel limits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    Pass,    // Inside tolerance
+    Warning, // Inside limits, but in the warning band next to them
+    Fail,    // Outside limits (or overload)
+}
+
+/// Tolerance of a channel: low and high limit with an optional warning band inside them,
+/// all in the unit of the channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub low: f64,
+    pub high: f64,
+    pub warning_band: f64,
+}
+
+impl Limits {
+    /// Accepts `LOW,HIGH` or `LOW,HIGH,WARNING_BAND` (i.e. `4.75,5.25,0.05`).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid limits '{}', expected 'LOW,HIGH' or 'LOW,HIGH,WARNING_BAND'",
+                text
+            )
+        };
+        let numbers = text
+            .split(',')
+            .map(|number| number.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| invalid())?;
+        let (low, high, warning_band) = match numbers[..] {
+            [low, high] => (low, high, 0.0),
+            [low, high, warning_band] => (low, high, warning_band),
+            _ => return Err(invalid()),
+        };
+        if !numbers.iter().all(|number| number.is_finite()) || low > high || warning_band < 0.0 {
+            return Err(invalid());
+        }
+        Ok(Limits {
+            low,
+            high,
+            warning_band,
+        })
+    }
+
+    /// Zone of the value, `None` for a reading without a value (invalid or underrange).
+    pub fn zone(&self, measurement: &Measurement) -> Option<Zone> {
+        if measurement.state.is_overload() {
+            return Some(Zone::Fail);
+        }
+        if !measurement.is_valid() {
+            return None;
+        }
+        Some(self.zone_of(measurement.value))
+    }
+
+    pub fn zone_of(&self, value: f64) -> Zone {
+        if value < self.low || value > self.high {
+            Zone::Fail
+        } else if value < self.low + self.warning_band || value > self.high - self.warning_band {
+            Zone::Warning
+        } else {
+            Zone::Pass
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::measurement::Unit;
+
+    #[test]
+    fn parses_limits() {
+        assert_eq!(
+            Limits::parse("4.75, 5.25"),
+            Ok(Limits {
+                low: 4.75,
+                high: 5.25,
+                warning_band: 0.0,
+            })
+        );
+        assert_eq!(
+            Limits::parse("-1,1,0.1"),
+            Ok(Limits {
+                low: -1.0,
+                high: 1.0,
+                warning_band: 0.1,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_limits() {
+        for text in [
+            "", "5", "1,2,3,4", "a,b", "5,4", "1,2,-0.1", "nan,1", "inf,5", "1,2,nan", "-inf,0",
+        ] {
+            assert!(Limits::parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn finds_zone_of_value() {
+        let limits = Limits::parse("4.75,5.25,0.05").unwrap();
+        assert_eq!(limits.zone_of(5.0), Zone::Pass);
+        assert_eq!(limits.zone_of(4.78), Zone::Warning);
+        assert_eq!(limits.zone_of(5.22), Zone::Warning);
+        assert_eq!(limits.zone_of(4.75), Zone::Warning);
+        assert_eq!(limits.zone_of(4.7), Zone::Fail);
+        assert_eq!(limits.zone_of(5.3), Zone::Fail);
+    }
+
+    #[test]
+    fn finds_zone_of_measurement() {
+        let limits = Limits::parse("4.75,5.25").unwrap();
+        let unit = Unit::parse("V");
+        let zone = |line: &[u8]| limits.zone(&Measurement::parse(line, &unit).unwrap());
+        assert_eq!(zone(b"5.0"), Some(Zone::Pass));
+        assert_eq!(zone(b"6.0"), Some(Zone::Fail));
+        assert_eq!(zone(b"9.9E37"), Some(Zone::Fail));
+        assert_eq!(zone(b"9.91E37"), None);
+    }
+}
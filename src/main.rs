@@ -2,44 +2,105 @@ use clap::{Arg, Command};
 use raylib::ffi::LoadFontFromMemory;
 use raylib::prelude::*;
 use std::ffi::CString;
-use std::io::{self, Write};
 use std::ptr::null_mut;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use csv::WriterBuilder;
 use std::fs::OpenOptions;
 use chrono::prelude::*;
 
+mod alarm;
+mod bargraph;
+mod channel;
+mod chart;
+mod format;
+mod gauge;
+mod history;
+mod kiosk;
+mod limits;
+mod measurement;
+mod placement;
+mod segment;
+mod settings;
+mod setup;
+mod statistics;
+mod theme;
+
+use alarm::{AlarmConfig, AlarmHook, AlarmKind};
+use bargraph::{BarGraph, BarScale};
+use channel::{Channel, ChannelAction, ChannelConfig};
+use chart::{draw_chart, parse_time, ChartLayout, ChartRange, ChartStyle, ChartView, ChartWindow};
+use format::{format_engineering, format_measurement, DisplayFormat, Resolution};
+use gauge::Gauge;
+use kiosk::{ButtonAction, ButtonBar};
+use limits::Limits;
+use measurement::{Measurement, MeasurementFlags, SiPrefix, Unit};
+use placement::{load_last_position, save_last_position, WindowPlacement};
+use segment::{draw_annunciators, SegmentDisplay, SegmentStyle};
+use settings::{load_profile, merge_options, SettingsEvent, SettingsPanel};
+use setup::load_setup;
+use statistics::Statistics;
+use theme::{parse_color, Theme};
+
 const SCREEN_WIDTH: i32 = 900;
 const SCREEN_HEIGHT: i32 = 150;
 const UNIT_SCREEN_WIDTH: f32 = 680.0;
 const DISPLAY_POS_10: f32 = 10.0;
 const DISPLAY_POS_20: f32 = 20.0;
 const DISPLAY_FONT_SIZE_140: f32 = 140.0;
-const DISPLAY_CHANNEL_COLOR: Color = Color::WHITE;
-const DISPLAY_BACKGROUND_COLOR: Color = Color::BLACK;
-
-const SERIAL_BUFFER_SIZE: i32 = 32;
-const SERIAL_TIMEOUT_MILISEC: i32 = 10;
-const SCPI_MEAS_CMD_OWON: &[u8; 6] = b"MEAS?\n";
-const SCPI_MEAS_CMD_AGILENT: &[u8; 14] = b"MEAS:VOLT:DC?\n"; // before SYST:REM
+const DISPLAY_SEGMENT_HEIGHT: f32 = 95.0;
+const DISPLAY_SEGMENT_POS_Y: f32 = 40.0;
+const DISPLAY_BARGRAPH_HEIGHT: i32 = 30;
+const WINDOW_MIN_WIDTH: i32 = 225;
+const WINDOW_MIN_HEIGHT: i32 = 40;
+const DISPLAY_SELECTED_COLOR: Color = Color::DARKGRAY;
+
+const STALE_TIMEOUT_MILISEC_DEFAULT: &str = "3000";
+const STALE_DASHES: &str = "---------";
+const STALE_DIM_ALPHA: f32 = 0.25;
+const ALARM_FLASH_PERIOD_SEC: f64 = 0.25;
+const ALARM_DIM_ALPHA: f32 = 0.2;
+const STATISTICS_POS_Y: f32 = 136.0;
+const STATISTICS_SIGNIFICANT_DIGITS: usize = 5;
 const APP_NAME: &str = "Open Modern Multimeter";
+// Hotkeys act on all channels at once
+const HOTKEYS: [(KeyboardKey, ChannelAction); 9] = [
+    (KeyboardKey::KEY_D, ChannelAction::Hold),
+    (KeyboardKey::KEY_Z, ChannelAction::Rel),
+    (KeyboardKey::KEY_R, ChannelAction::Reset),
+    (KeyboardKey::KEY_SPACE, ChannelAction::Pause),
+    (KeyboardKey::KEY_H, ChannelAction::Chart("h")),
+    (KeyboardKey::KEY_L, ChannelAction::Chart("l")),
+    (KeyboardKey::KEY_N, ChannelAction::Chart("")),
+    (KeyboardKey::KEY_W, ChannelAction::CycleChartWindow),
+    (KeyboardKey::KEY_A, ChannelAction::AckAlarm),
+];
 
 #[derive(Debug)]
 pub struct Config {
-    port_name: String,
-    baud_rate: u32,
-    channel_no: u32,
-    unit: String,
-    window_position: String,
+    channels: Vec<ChannelConfig>,
+    window_position: WindowPlacement,
+    monitor: Option<i32>,
     scpi_protocol_enabled: bool,
     enable_chart: String,
-    color: Color,
+    chart_views: Vec<ChartView>, // For each channel
+    theme: Theme,
     enable_csv_logger: bool,
+    stale_timeout: Duration,
+    stale_dashes: bool,
+    display_format: DisplayFormat,
+    segment_style: Option<SegmentStyle>,
+    window_size: Option<(i32, i32)>,
+    always_on_top: bool,
+    borderless: bool,
+    background: Option<Color>, // Transparent window when not set
+    kiosk: bool,
+    statistics_line: bool,
+    alarm_command: Option<String>,
+    alarm_fifo: Option<String>,
 }
 
 impl Config {
     pub fn new(matches: &clap::ArgMatches) -> Result<Self, String> {
-        let port_name = matches.value_of("port").unwrap().to_string();
         let baud_rate = matches
             .value_of("baud")
             .unwrap()
@@ -50,15 +111,115 @@ impl Config {
             .unwrap()
             .parse::<u32>()
             .map_err(|_| "Invalid channel number".to_string())?;
-        let unit = matches.value_of("unit").unwrap().to_string();
-        let window_position = matches.value_of("window_position").unwrap().to_string();
-        let enable_chart = matches.value_of("enable_chart").unwrap().to_string();
-        let color = match matches.value_of("color") {
-            Some("r") => Color::RED,
-            Some("g") => Color::GREEN,
-            Some("b") => Color::BLUE,
-            _ => Color::RED,
+        // Every port is a separate channel, numbered from `channel_no`; the last unit is
+        // used for ports without their own unit
+        let units: Vec<Unit> = matches
+            .values_of("unit")
+            .unwrap()
+            .map(Unit::parse)
+            .collect();
+        let limits: Vec<Option<Limits>> = matches
+            .values_of("limits")
+            .unwrap()
+            .map(|limits| match limits {
+                "" => Ok(None),
+                limits => Limits::parse(limits).map(Some),
+            })
+            .collect::<Result<_, _>>()?;
+        let alarms: Vec<AlarmConfig> = matches
+            .values_of("alarm")
+            .unwrap()
+            .map(|alarm| match alarm {
+                "" => Ok(AlarmConfig::default()),
+                alarm => AlarmConfig::parse(alarm),
+            })
+            .collect::<Result<_, _>>()?;
+        let resolutions: Vec<Option<Resolution>> = matches
+            .values_of("resolution")
+            .unwrap()
+            .map(|resolution| match resolution {
+                "" => Ok(None),
+                resolution => Resolution::parse(resolution).map(Some),
+            })
+            .collect::<Result<_, _>>()?;
+        let bar_scale = match matches.value_of("bargraph") {
+            Some("off") | Some("") | None => None,
+            Some(scale) => Some(BarScale::parse(scale)?),
+        };
+        let gauge_scale = match matches.value_of("view") {
+            Some("gauge") => Some(BarScale::parse(matches.value_of("gauge_range").unwrap())?),
+            _ => None,
+        };
+        let digits_update = matches
+            .value_of("digits_update")
+            .unwrap()
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| "Invalid digits update time".to_string())?;
+        let channels: Vec<ChannelConfig> = matches
+            .values_of("port")
+            .unwrap()
+            .enumerate()
+            .map(|(i, port_name)| ChannelConfig {
+                port_name: port_name.to_string(),
+                baud_rate,
+                channel_no: channel_no + i as u32,
+                unit: units[i.min(units.len() - 1)].clone(),
+                limits: limits[i.min(limits.len() - 1)],
+                alarm: alarms[i.min(alarms.len() - 1)].clone(),
+                resolution: resolutions[i.min(resolutions.len() - 1)],
+                bar_scale,
+                gauge_scale,
+                digits_update,
+            })
+            .collect();
+        let window_position = WindowPlacement::parse(matches.value_of("window_position").unwrap())?;
+        let monitor = match matches.value_of("monitor") {
+            Some("") | None => None,
+            Some(monitor) => Some(
+                monitor
+                    .parse::<i32>()
+                    .map_err(|_| format!("Invalid monitor index '{}'", monitor))?,
+            ),
         };
+        let enable_chart = matches.value_of("enable_chart").unwrap().to_string();
+        let chart_window = ChartWindow::parse(matches.value_of("chart_window").unwrap())?;
+        let chart_range = ChartRange::parse(matches.value_of("chart_y_range").unwrap())?;
+        let chart_styles: Vec<ChartStyle> = matches
+            .values_of("chart_style")
+            .unwrap()
+            .map(ChartStyle::parse)
+            .collect::<Result<_, _>>()?;
+        let chart_averages: Vec<Option<Duration>> = matches
+            .values_of("chart_average")
+            .unwrap()
+            .map(|average| match average {
+                "" => Ok(None),
+                average => parse_time(average).map(Some).ok_or_else(|| {
+                    format!(
+                        "Invalid chart average '{}', expected a time like 10s, 1m",
+                        average
+                    )
+                }),
+            })
+            .collect::<Result<_, _>>()?;
+        let chart_views = (0..channels.len())
+            .map(|i| ChartView {
+                window: chart_window,
+                range: chart_range,
+                style: chart_styles[i.min(chart_styles.len() - 1)],
+                average: chart_averages[i.min(chart_averages.len() - 1)],
+            })
+            .collect();
+        let mut theme = Theme::named(matches.value_of("theme").unwrap())?;
+        match matches.value_of("theme_file") {
+            Some("") | None => {}
+            Some(theme_file) => theme.load(theme_file)?,
+        }
+        match matches.value_of("color") {
+            Some("") | None => {}
+            Some(color) => theme.digits = parse_color(color)?,
+        }
         let scpi_protocol_enabled = match matches.value_of("scpi_protocol_enabled") {
             Some("1") => true,
             Some("0") => false,
@@ -69,20 +230,109 @@ impl Config {
             Some("0") => false,
             _ => false,
         };
+        let stale_timeout = matches
+            .value_of("stale_timeout")
+            .unwrap()
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|_| "Invalid stale timeout".to_string())?;
+        let stale_dashes = match matches.value_of("stale_indication") {
+            Some("dashes") => true,
+            Some("dim") => false,
+            _ => false,
+        };
+
+        let significant_digits = matches
+            .value_of("significant_digits")
+            .unwrap()
+            .parse::<usize>()
+            .map_err(|_| "Invalid number of significant digits".to_string())?;
+        let locked_prefix = match matches.value_of("eng_range") {
+            Some("auto") | None => None,
+            Some("base") => Some(SiPrefix::None),
+            Some(symbol) => Some(
+                SiPrefix::from_symbol(symbol)
+                    .ok_or_else(|| format!("Invalid engineering range '{}'", symbol))?,
+            ),
+        };
+        let display_format = match matches.value_of("display_format") {
+            Some("eng") => DisplayFormat::Engineering {
+                significant_digits,
+                locked_prefix,
+            },
+            _ => DisplayFormat::Raw,
+        };
+
+        let segment_style = match matches.value_of("renderer") {
+            Some("seg7") => Some(SegmentStyle::Seven),
+            Some("seg14") => Some(SegmentStyle::Fourteen),
+            _ => None,
+        };
+
+        let window_size = match matches.value_of("window_size") {
+            Some("") | None => None,
+            Some(size) => {
+                let invalid = || format!("Invalid window size '{}', expected WIDTHxHEIGHT", size);
+                let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+                Some((
+                    width.parse::<i32>().map_err(|_| invalid())?,
+                    height.parse::<i32>().map_err(|_| invalid())?,
+                ))
+            }
+        };
+
+        let kiosk = matches.value_of("kiosk") == Some("1");
+        let statistics_line = matches.value_of("statistics_line") == Some("1");
+        let alarm_command = match matches.value_of("alarm_command") {
+            Some("") | None => None,
+            Some(command) => Some(command.to_string()),
+        };
+        let alarm_fifo = match matches.value_of("alarm_fifo") {
+            Some("") | None => None,
+            Some(fifo) => Some(fifo.to_string()),
+        };
+        let always_on_top = matches.value_of("always_on_top") == Some("1");
+        let borderless = matches.value_of("borderless") == Some("1");
+        let background = match matches.value_of("background") {
+            Some("") | None => Some(theme.background),
+            Some("transparent") => None,
+            Some(color) => Some(parse_color(color)?),
+        };
 
         Ok(Config {
-            port_name,
-            baud_rate,
-            channel_no,
-            unit,
+            channels,
             window_position,
+            monitor,
             scpi_protocol_enabled,
             enable_chart,
-            color,
+            chart_views,
+            theme,
             enable_csv_logger,
+            stale_timeout,
+            stale_dashes,
+            display_format,
+            segment_style,
+            window_size,
+            always_on_top,
+            borderless,
+            background,
+            kiosk,
+            statistics_line,
+            alarm_command,
+            alarm_fifo,
         })
     }
 
+    /// Whether the window has to be placed again for the new configuration.
+    fn window_changed(&self, config: &Config) -> bool {
+        self.window_position != config.window_position
+            || self.monitor != config.monitor
+            || self.window_size != config.window_size
+            || self.always_on_top != config.always_on_top
+            || self.borderless != config.borderless
+            || self.kiosk != config.kiosk
+    }
+
     fn validate_number(val: &str) -> Result<(), String> {
         val.parse::<i32>()
             .map(|_| ())
@@ -96,12 +346,128 @@ impl Config {
     }
 }
 
+/// Positions and sizes of display elements of one channel, scaled proportionally to the area
+/// of the window given to the channel.
+struct Layout {
+    scale: f32,
+    area: Rectangle,
+    display_height: f32, // Height of the panel with the value, chart is below it
+}
+
+impl Layout {
+    fn new(area: Rectangle, chart_enabled: bool, bargraph_enabled: bool) -> Self {
+        let base_height = Layout::base_height(chart_enabled, bargraph_enabled);
+        let scale = (area.width / SCREEN_WIDTH as f32).min(area.height / base_height as f32);
+        Layout {
+            scale,
+            area,
+            display_height: Layout::base_height(false, bargraph_enabled) as f32 * scale,
+        }
+    }
+
+    /// Height of a channel for the default window size.
+    fn base_height(chart_enabled: bool, bargraph_enabled: bool) -> i32 {
+        let mut height = SCREEN_HEIGHT;
+        if bargraph_enabled {
+            height += DISPLAY_BARGRAPH_HEIGHT;
+        }
+        if chart_enabled {
+            height += SCREEN_HEIGHT;
+        }
+        height
+    }
+
+    /// Height of all channels for the default window size.
+    fn total_height(channels: &[Channel]) -> i32 {
+        channels
+            .iter()
+            .map(|channel| {
+                Layout::base_height(!channel.chart.is_empty(), channel.bargraph.is_some())
+            })
+            .sum()
+    }
+
+    /// Splits the window area into equal rows, one for each channel.
+    fn rows(area: Rectangle, channels: &[Channel]) -> Vec<Self> {
+        let row_height = area.height / channels.len().max(1) as f32;
+        channels
+            .iter()
+            .enumerate()
+            .map(|(i, channel)| {
+                Layout::new(
+                    Rectangle::new(
+                        area.x,
+                        area.y + i as f32 * row_height,
+                        area.width,
+                        row_height,
+                    ),
+                    !channel.chart.is_empty(),
+                    channel.bargraph.is_some(),
+                )
+            })
+            .collect()
+    }
+
+    /// Scales a position or size given for the default window size.
+    fn px(&self, base: f32) -> f32 {
+        base * self.scale
+    }
+
+    /// Scales a position given for the default window size, relative to the channel area.
+    fn pos(&self, base_x: f32, base_y: f32) -> Vector2 {
+        Vector2::new(self.area.x + self.px(base_x), self.area.y + self.px(base_y))
+    }
+
+    /// Area of the display panel with the value.
+    fn display(&self) -> Rectangle {
+        Rectangle::new(
+            self.area.x,
+            self.area.y,
+            self.area.width,
+            self.display_height.min(self.area.height),
+        )
+    }
+
+    /// Area for charts, below the display panel.
+    fn chart(&self) -> Rectangle {
+        Rectangle::new(
+            self.area.x,
+            self.area.y + self.display_height,
+            self.area.width,
+            (self.area.height - self.display_height).max(0.0),
+        )
+    }
+
+    /// Plot area of the linear chart, inside the axis labels.
+    fn plot(&self) -> Rectangle {
+        let chart = self.chart();
+        Rectangle::new(
+            chart.x + self.px(80.0),
+            chart.y + self.px(10.0),
+            chart.width - self.px(100.0),
+            chart.height - self.px(60.0),
+        )
+    }
+}
+
+/// Texts and color of the reading shown on the display.
+struct Readout<'a> {
+    value: &'a str,
+    unit: &'a Unit,
+    flags: MeasurementFlags,
+    color: Color,
+    bargraph: Option<&'a BarGraph>,
+    gauge: Option<&'a Gauge>, // Shown instead of the digits and unit
+}
+
 struct Display {
     font: Font,
+    label_color: Color,
+    segment_style: Option<SegmentStyle>, // Vector segments instead of the font for the value
 }
 
 impl Display {
-    fn new(font_file: &[u8]) -> Self {
+    fn new(font_file: &[u8], segment_style: Option<SegmentStyle>, label_color: Color) -> Self {
         let font_file_size = font_file.len();
         let font_type = CString::new(".ttf").unwrap();
         let chars = null_mut();
@@ -115,83 +481,293 @@ impl Display {
                 100,
             ))
         };
-        Display { font }
+        Display {
+            font,
+            label_color,
+            segment_style,
+        }
     }
 
     fn draw(
         &self,
         d: &mut RaylibDrawHandle<'_>,
+        layout: &Layout,
         channel_no: u32,
-        value: &str,
-        unit: &str,
-        color: &Color,
+        readout: &Readout<'_>,
     ) {
         d.draw_text_ex(
             &self.font,
             &format!("CH:{}", channel_no),
-            Vector2::new(DISPLAY_POS_10, DISPLAY_POS_10),
-            DISPLAY_POS_20,
-            DISPLAY_POS_10,
-            DISPLAY_CHANNEL_COLOR,
+            layout.pos(DISPLAY_POS_10, DISPLAY_POS_10),
+            layout.px(DISPLAY_POS_20),
+            layout.px(DISPLAY_POS_10),
+            self.label_color,
         );
-        d.draw_text_ex(
-            &self.font,
-            &value,
-            Vector2::new(DISPLAY_POS_20 * 2.0, DISPLAY_POS_20),
-            DISPLAY_FONT_SIZE_140,
-            DISPLAY_POS_10,
-            color,
+        draw_annunciators(
+            d,
+            layout.pos(DISPLAY_POS_20 * 5.0, DISPLAY_POS_10),
+            layout.px(DISPLAY_POS_10) as i32,
+            readout.unit,
+            &readout.flags,
+            readout.color,
         );
+        if let Some(gauge) = readout.gauge {
+            gauge.draw(
+                d,
+                Rectangle::new(
+                    layout.area.x,
+                    layout.area.y,
+                    layout.area.width,
+                    layout.px(SCREEN_HEIGHT as f32),
+                ),
+                layout.scale,
+                readout.unit,
+                readout.color,
+                self.label_color,
+            );
+        } else {
+            self.draw_value(d, layout, readout);
+        }
+        if let Some(bargraph) = readout.bargraph {
+            let pos = layout.pos(DISPLAY_POS_20 * 2.0, SCREEN_HEIGHT as f32);
+            bargraph.draw(
+                d,
+                Rectangle::new(
+                    pos.x,
+                    pos.y,
+                    layout.px(SCREEN_WIDTH as f32 - DISPLAY_POS_20 * 4.0),
+                    layout.px(DISPLAY_BARGRAPH_HEIGHT as f32 - DISPLAY_POS_10),
+                ),
+                readout.color,
+            );
+        }
+    }
+
+    /// Draws the value with segmented digits (or the font) and the unit next to it.
+    fn draw_value(&self, d: &mut RaylibDrawHandle<'_>, layout: &Layout, readout: &Readout<'_>) {
+        match self.segment_style {
+            Some(style) => SegmentDisplay::new(style, layout.px(DISPLAY_SEGMENT_HEIGHT)).draw_text(
+                d,
+                readout.value,
+                layout.pos(DISPLAY_POS_20 * 2.0, DISPLAY_SEGMENT_POS_Y),
+                readout.color,
+            ),
+            None => d.draw_text_ex(
+                &self.font,
+                readout.value,
+                layout.pos(DISPLAY_POS_20 * 2.0, DISPLAY_POS_20),
+                layout.px(DISPLAY_FONT_SIZE_140),
+                layout.px(DISPLAY_POS_10),
+                readout.color,
+            ),
+        }
         d.draw_text_ex(
             &self.font,
-            &unit,
-            Vector2::new(UNIT_SCREEN_WIDTH, DISPLAY_POS_20),
-            DISPLAY_FONT_SIZE_140,
-            DISPLAY_POS_10,
-            color,
+            &readout.unit.to_string(),
+            layout.pos(UNIT_SCREEN_WIDTH, DISPLAY_POS_20),
+            layout.px(DISPLAY_FONT_SIZE_140),
+            layout.px(DISPLAY_POS_10),
+            readout.color,
+        );
+    }
+
+    /// Draws MIN, MAX, AVG, standard deviation, count of readings and time since reset in a line
+    /// under the value.
+    fn draw_statistics(
+        &self,
+        d: &mut RaylibDrawHandle<'_>,
+        layout: &Layout,
+        statistics: &Statistics,
+        unit: &Unit,
+    ) {
+        let format_value = |value: Option<f64>| match value {
+            Some(value) if value.is_finite() => {
+                let (text, prefix) = format_engineering(
+                    value * unit.prefix.factor(),
+                    STATISTICS_SIGNIFICANT_DIGITS,
+                    None,
+                );
+                let unit = Unit {
+                    prefix,
+                    ..unit.clone()
+                };
+                format!("{} {}", text, unit)
+            }
+            _ => "-".to_string(),
+        };
+        let elapsed = statistics.elapsed().as_secs();
+        let text = format!(
+            "MIN {}   MAX {}   AVG {}   SD {}   N {}   T {:02}:{:02}:{:02}",
+            format_value(Some(statistics.min)),
+            format_value(Some(statistics.max)),
+            format_value(statistics.average()),
+            format_value(statistics.std_dev()),
+            statistics.count,
+            elapsed / 3600,
+            elapsed / 60 % 60,
+            elapsed % 60
+        );
+        let pos = layout.pos(DISPLAY_POS_10, STATISTICS_POS_Y);
+        d.draw_text(
+            &text,
+            pos.x as i32,
+            pos.y as i32,
+            layout.px(DISPLAY_POS_10) as i32,
+            self.label_color,
+        );
+    }
+
+    /// Draws reading health (age of the last valid reading, timeouts and parse failures)
+    /// in the top right corner, next to the channel label.
+    /// Latched alarms are appended to the status.
+    fn draw_health(
+        &self,
+        d: &mut RaylibDrawHandle<'_>,
+        layout: &Layout,
+        health: &ReadingHealth,
+        paused: bool,
+        alarms: &[AlarmKind],
+    ) {
+        let age = match health.age() {
+            _ if !health.connected => "NO PORT".to_string(),
+            _ if paused => "PAUSED".to_string(),
+            Some(age) if health.is_stale() => format!("STALE {:.1}s", age.as_secs_f32()),
+            Some(_) => "LIVE".to_string(),
+            None => "NO DATA".to_string(),
+        };
+        let mut status = format!(
+            "{}  TO:{}  ERR:{}",
+            age, health.timeouts, health.parse_failures
+        );
+        if !alarms.is_empty() {
+            let names: Vec<&str> = alarms.iter().map(|kind| kind.name()).collect();
+            status.push_str(&format!("  ALARM:{}", names.join(",")));
+        }
+        let status_color = if !alarms.is_empty() {
+            Color::RED
+        } else if (health.is_stale() && !paused) || !health.connected {
+            Color::ORANGE
+        } else {
+            self.label_color
+        };
+        let pos = layout.pos(UNIT_SCREEN_WIDTH - 140.0, DISPLAY_POS_10);
+        d.draw_text(
+            &status,
+            pos.x as i32,
+            pos.y as i32,
+            layout.px(DISPLAY_POS_10) as i32,
+            status_color,
         );
     }
 }
 
-fn get_screen_resolution() -> (i32, i32) {
-    unsafe {
-        let monitor_index = raylib::ffi::GetCurrentMonitor();
-        let width = raylib::ffi::GetMonitorWidth(monitor_index);
-        let height = raylib::ffi::GetMonitorHeight(monitor_index);
-        (width, height)
+/// Moves a window without decorations, when dragged by the display panel with the left mouse button.
+struct WindowDrag {
+    grab: Option<Vector2>, // Mouse position in the window, where it was grabbed
+}
+
+impl WindowDrag {
+    fn new() -> Self {
+        Self { grab: None }
+    }
+
+    fn update(&mut self, rl: &mut RaylibHandle, layouts: &[Layout]) {
+        let mouse = rl.get_mouse_position();
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON)
+            && layouts
+                .iter()
+                .any(|layout| layout.display().check_collision_point_rec(mouse))
+        {
+            self.grab = Some(mouse);
+        }
+        if !rl.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
+            self.grab = None;
+        }
+        if let Some(grab) = self.grab {
+            let position = rl.get_window_position() + (mouse - grab);
+            rl.set_window_position(position.x as i32, position.y as i32);
+        }
     }
 }
 
-fn calculate_window_position(
-    position_code: &str,
-    screen_width: i32,
-    screen_height: i32,
-    window_width: i32,
-    window_height: i32,
-) -> (i32, i32) {
-    let parts: Vec<&str> = position_code.split('_').collect();
-    if parts.len() != 2 {
-        panic!("Invalid position code format. Expected format: 'X_Y'.");
+/// Tracks how fresh the displayed reading is, so a frozen value is never taken for a live one.
+struct ReadingHealth {
+    stale_timeout: Duration,
+    last_valid: Option<Instant>,
+    waiting_since: Instant, // Start or the last port open, for the age without a valid reading
+    timeouts: u32,          // Number of times the reading went stale
+    parse_failures: u32,    // Number of received lines which were not a valid reading
+    timed_out: bool,
+    connected: bool, // Serial port is open
+}
+
+impl ReadingHealth {
+    fn new(stale_timeout: Duration) -> Self {
+        Self {
+            stale_timeout,
+            last_valid: None,
+            waiting_since: Instant::now(),
+            timeouts: 0,
+            parse_failures: 0,
+            timed_out: false,
+            connected: false,
+        }
+    }
+
+    fn record_valid(&mut self) {
+        self.last_valid = Some(Instant::now());
+        self.timed_out = false;
     }
 
-    let horizontal_section: i32 = parts[0].parse().expect("Invalid horizontal section.");
-    let vertical_section: i32 = parts[1].parse().expect("Invalid vertical section.");
+    fn record_connected(&mut self) {
+        self.connected = true;
+        self.waiting_since = Instant::now();
+    }
 
-    if horizontal_section < 1
-        || horizontal_section > 4
-        || vertical_section < 1
-        || vertical_section > 4
-    {
-        panic!("Position sections must be between 1 and 4.");
+    fn record_parse_failure(&mut self) {
+        self.parse_failures += 1;
     }
 
-    let section_width = screen_width / 4;
-    let section_height = screen_height / 4;
+    /// Counts a timeout once per stale period (not once per frame).
+    fn update(&mut self) {
+        if self.is_stale() && !self.timed_out {
+            self.timed_out = true;
+            self.timeouts += 1;
+        }
+    }
 
-    let x = (horizontal_section - 1) * section_width + (section_width - window_width) / 2;
-    let y = (vertical_section - 1) * section_height + (section_height - window_height) / 2;
+    fn age(&self) -> Option<Duration> {
+        self.last_valid.map(|last_valid| last_valid.elapsed())
+    }
+
+    /// A meter which never answered is stale once the timeout passed since start (or since
+    /// its port was opened).
+    fn is_stale(&self) -> bool {
+        let age = self.age().unwrap_or_else(|| self.waiting_since.elapsed());
+        age > self.stale_timeout
+    }
+}
 
-    (x.max(0), y.max(0))
+/// Returns area `(x, y, width, height)` of the monitor, by default the one with the window.
+fn get_monitor_area(monitor: Option<i32>) -> Result<(i32, i32, i32, i32), String> {
+    unsafe {
+        let monitor_count = raylib::ffi::GetMonitorCount();
+        let monitor_index = match monitor {
+            Some(index) if index < 0 || index >= monitor_count => {
+                return Err(format!(
+                    "Invalid monitor index {}, available monitors: 0..{}",
+                    index,
+                    monitor_count - 1
+                ))
+            }
+            Some(index) => index,
+            None => raylib::ffi::GetCurrentMonitor(),
+        };
+        let position = raylib::ffi::GetMonitorPosition(monitor_index);
+        let width = raylib::ffi::GetMonitorWidth(monitor_index);
+        let height = raylib::ffi::GetMonitorHeight(monitor_index);
+        Ok((position.x as i32, position.y as i32, width, height))
+    }
 }
 
 /*
@@ -222,13 +798,13 @@ fn read_serial_data(port: &mut dyn serialport::SerialPort, serial_buf: &mut Vec<
 
 struct Histogram {
     bins: Vec<u32>,   // Frequency count for each bin
-    min_value: f32,   // Minimum value represented in the histogram
-    max_value: f32,   // Maximum value represented in the histogram
+    min_value: f64,   // Minimum value represented in the histogram
+    max_value: f64,   // Maximum value represented in the histogram
     bin_count: usize, // Number of bins
 }
 
 impl Histogram {
-    fn new(min_value: f32, max_value: f32, bin_count: usize) -> Self {
+    fn new(min_value: f64, max_value: f64, bin_count: usize) -> Self {
         Self {
             bins: vec![0; bin_count],
             min_value,
@@ -237,22 +813,22 @@ impl Histogram {
         }
     }
 
-    fn add_value(&mut self, value: f32) {
+    fn add_value(&mut self, value: f64) {
         if value < self.min_value || value > self.max_value {
             return;
         }
         let bin_index = ((value - self.min_value) / (self.max_value - self.min_value)
-            * self.bin_count as f32) as usize;
-        self.bins[bin_index] += 1;
+            * self.bin_count as f64) as usize;
+        // `max_value` itself belongs to the last bin
+        self.bins[bin_index.min(self.bin_count - 1)] += 1;
     }
 
-    /*
-        fn reset(&mut self) {
-            for bin in &mut self.bins {
-                *bin = 0;
-            }
+    fn reset(&mut self) {
+        for bin in &mut self.bins {
+            *bin = 0;
         }
-    */
+    }
+
     fn normalized_bins(&self) -> Vec<f32> {
         let max_count = *self.bins.iter().max().unwrap_or(&1) as f32;
         self.bins
@@ -265,23 +841,20 @@ impl Histogram {
 fn render_histogram(
     d: &mut RaylibDrawHandle<'_>,
     histogram: &Histogram,
-    pos_x: i32,
-    pos_y: i32,
-    screen_width: i32,
-    screen_height: i32,
+    area: Rectangle,
     bar_graph_thinkness: f32,
     bar_color: Color,
 ) {
-    let bin_width = (screen_width as f32 / histogram.bin_count as f32) * bar_graph_thinkness;
+    let bin_width = (area.width / histogram.bin_count as f32) * bar_graph_thinkness;
     let normalized_bins = histogram.normalized_bins();
 
     for (i, &bin_height) in normalized_bins.iter().enumerate() {
         let x = i as f32 * bin_width;
-        let y = screen_height as f32 - (bin_height * 100.0);
-        let bar_height = screen_height as f32 - y;
+        let y = area.height * (1.0 - bin_height);
+        let bar_height = area.height - y;
         d.draw_rectangle(
-            pos_x + x as i32,
-            pos_y + y as i32,
+            area.x as i32 + x as i32,
+            area.y as i32 + y as i32,
             bin_width as i32,
             bar_height as i32,
             bar_color,
@@ -289,168 +862,39 @@ fn render_histogram(
     }
 }
 
-fn draw_chart(
-    d: &mut RaylibDrawHandle<'_>,
-    chart_pos: Vector2,
-    chart_width: i32,
-    chart_height: i32,
-    x_label: &str,
-    y_label: &str,
-    data_points: &[(f32, f32)],
-    point_circle_size: f32,
-    grid_step: f32,
-    point_color: Color,
-    axis_color: Color,
-    grid_color: Color,
-    label_color: Color,
-) {
-    let x_axis_y = chart_pos.y + chart_height as f32;
-    let y_axis_x = chart_pos.x;
-
-    d.draw_line(
-        y_axis_x as i32,
-        x_axis_y as i32,
-        (y_axis_x + chart_width as f32) as i32,
-        x_axis_y as i32,
-        axis_color,
-    );
-
-    d.draw_line(
-        y_axis_x as i32,
-        x_axis_y as i32,
-        y_axis_x as i32,
-        (x_axis_y - chart_height as f32) as i32,
-        axis_color,
-    );
-
-    d.draw_text(
-        x_label,
-        (chart_pos.x + chart_width as f32 / 2.0) as i32,
-        (x_axis_y + 10.0) as i32,
-        20,
-        label_color,
-    );
-
-    d.draw_text(
-        y_label,
-        (y_axis_x - 25.0) as i32,
-        (chart_pos.y + chart_height as f32 / 2.0) as i32,
-        20,
-        label_color,
-    );
-
-    let max_x = data_points
-        .iter()
-        .map(|(x, _)| *x)
-        .fold(0.0 / 0.0, f32::max);
-    let max_y = data_points
-        .iter()
-        .map(|(_, y)| *y)
-        .fold(0.0 / 0.0, f32::max);
-    let scale_x = chart_width as f32 / max_x.max(1.0);
-    let scale_y = chart_height as f32 / max_y.max(1.0);
-
-    for &(x, y) in data_points {
-        let scaled_x = chart_pos.x + x * scale_x;
-        let scaled_y = x_axis_y - y * scale_y;
-        d.draw_circle(
-            scaled_x as i32,
-            scaled_y as i32,
-            point_circle_size,
-            point_color,
-        );
-    }
-
-    for i in 1..=((chart_width as f32 / grid_step) as i32) {
-        let x = y_axis_x + i as f32 * grid_step;
-        d.draw_line(
-            x as i32,
-            x_axis_y as i32,
-            x as i32,
-            (x_axis_y - chart_height as f32) as i32,
-            grid_color,
-        );
-    }
-    for i in 1..=((chart_height as f32 / grid_step) as i32) {
-        let y = x_axis_y - i as f32 * grid_step;
-        d.draw_line(
-            y_axis_x as i32,
-            y as i32,
-            (y_axis_x + chart_width as f32) as i32,
-            y as i32,
-            grid_color,
-        );
-    }
-}
-
-fn join(a: &[u8]) -> String {
-    use std::fmt::Write;
-    a.iter().fold(String::new(), |mut s, &n| {
-        write!(s, "{}", (n as u8) as char).ok();
-        s
-    })
+fn append_to_csv(file_path: &str, measurement: &Measurement) -> Result<(), Box<dyn std::error::Error>> {
+    let value = if measurement.is_valid() {
+        measurement.value.to_string()
+    } else {
+        String::new()
+    };
+    append_record_to_csv(
+        file_path,
+        &[
+            measurement.timestamp.timestamp_millis().to_string(),
+            value,
+            measurement.state.log_flag().to_string(),
+        ],
+    )
 }
 
-/// Converts a scientific notation value stored in `Vec<u8>` into a float representation in `Vec<u8>`.
-fn convert_scientific_to_float(input: &[u8]) -> Vec<u8> {
-    // Check for 'E' or 'e' in the input
-    if input.contains(&b'E') || input.contains(&b'e') {
-        // Attempt to parse the Vec<u8> as a UTF-8 string
-        match String::from_utf8(input.to_vec()) {
-            Ok(string) => match string.trim().parse::<f32>() {
-                Ok(num) => {
-                    // Format the parsed number back to Vec<u8>
-                    return format!("{:.8}", num).into_bytes();
-                }
-                Err(e) => {
-                    eprintln!("Failed to parse number: {}", e);
-                }
-            },
-            Err(e) => {
-                eprintln!("Invalid UTF-8 data: {}", e);
-            }
-        }
-    }
-    // If parsing fails or 'E'/'e' is not found, return the input unchanged
-    input.to_vec()
+/// Logs a tripped alarm as an event line, with `ALARM_<kind>` in place of the reading flag.
+fn append_alarm_to_csv(
+    file_path: &str,
+    kind: AlarmKind,
+    value: Option<f64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    append_record_to_csv(
+        file_path,
+        &[
+            Utc::now().timestamp_millis().to_string(),
+            value.map(|value| value.to_string()).unwrap_or_default(),
+            format!("ALARM_{}", kind.name()),
+        ],
+    )
 }
 
-/// Converts a scientific notation value stored in `Vec<u8>` into a float representation as a `String`.
-fn convert_scientific_to_float2(input: &[u8]) -> Result<String, String> {
-    // Check for 'E' or 'e' in the input
-    if input.contains(&b'E') || input.contains(&b'e') {
-        // Attempt to parse the Vec<u8> as a UTF-8 string
-        match String::from_utf8(input.to_vec()) {
-            Ok(string) => match string.trim().parse::<f32>() {
-                Ok(num) => {
-                    // Format the parsed number back to a readable string
-                    let formatted = format!("{:.8}", num);
-                    return Ok(join(&formatted.into_bytes())); // Return owned String
-                }
-                Err(e) => {
-                    return Err(format!("Failed to parse number: {}", e));
-                }
-            },
-            Err(e) => {
-                return Err(format!("Invalid UTF-8 data: {}", e));
-            }
-        }
-    }
-    // If parsing fails or 'E'/'e' is not found, return the original as a String
-    Ok(join2(input))
-}
-
-/// Joins a byte array into a readable string format.
-fn join2(a: &[u8]) -> String {
-    use std::fmt::Write;
-    a.iter().fold(String::new(), |mut s, &n| {
-        write!(s, "{}", n as char).ok();
-        s
-    })
-}
-
-
-fn append_to_csv(file_path: &str, timestamp: i64, measurement: f32) -> Result<(), Box<dyn std::error::Error>> {
+fn append_record_to_csv(file_path: &str, record: &[String]) -> Result<(), Box<dyn std::error::Error>> {
     let file = OpenOptions::new()
         .create(true)
         .append(true)
@@ -458,16 +902,16 @@ fn append_to_csv(file_path: &str, timestamp: i64, measurement: f32) -> Result<()
     let mut wtr = WriterBuilder::new()
         .has_headers(false)
         .from_writer(file);
-    wtr.write_record(&[timestamp.to_string(), measurement.to_string()])?;
+    wtr.write_record(record)?;
     wtr.flush()?;
     
     Ok(())
 }
 
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let now = Utc::now().timestamp_millis();
-    let matches = Command::new(APP_NAME)
+/// Command line options; the settings panel edits them while running.
+fn command() -> Command<'static> {
+    Command::new(APP_NAME)
         .about(
             "Reads values from an external multimeter via a serial port and displays measurement values in real-time in a UI",
         )
@@ -477,9 +921,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Arg::new("port")
                 .short('p')
                 .long("port")
-                .help("The device path to the serial port")
+                .help("The device path to the serial port (repeat for more channels, i.e. -p /dev/ttyUSB0 -p /dev/ttyUSB1)")
                 .takes_value(true)
-                .required(true),
+                .multiple_occurrences(true)
+                .required_unless_present_any(["profile", "setup"]),
         )
         .arg(
             Arg::new("baud")
@@ -487,7 +932,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .long("baud")
                 .help("The baud rate for communication")
                 .takes_value(true)
-                .required(true)
+                .required_unless_present_any(["profile", "setup"])
                 .validator(Config::valid_baud),
         )
         .arg(
@@ -496,24 +941,99 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .long("channel_no")
                 .help("The channel number to display")
                 .takes_value(true)
-                .required(true)
+                .required_unless_present_any(["profile", "setup"])
                 .validator(Config::validate_number),
         )
         .arg(
             Arg::new("unit")
                 .short('u')
                 .long("unit")
-                .help("The unit of measurement")
+                .help("The unit of measurement (repeat for each port, the last one is used for remaining ports)")
                 .takes_value(true)
-                .required(true),
+                .multiple_occurrences(true)
+                .required_unless_present_any(["profile", "setup"]),
+        )
+        .arg(
+            Arg::new("limits")
+                .long("limits")
+                .help("Limits of the value <low>,<high>[,<warning_band>] in the unit of measurement (i.e. 4.75,5.25,0.05): digits and chart points are colored by zone - inside tolerance, in the warning band next to limits or outside limits (repeat for each port, the last one is used for remaining ports)")
+                .required(false)
+                .multiple_occurrences(true)
+                .default_value(""),
+        )
+        .arg(
+            Arg::new("bargraph")
+                .long("bargraph")
+                .help("Analog bar graph under the value with peak markers: off, auto (scale from the range of the value), auto_zero (zero-centered auto scale for signed values) or fixed scale <min>,<max> (i.e. -5,5)")
+                .required(false)
+                .default_value("off"),
+        )
+        .arg(
+            Arg::new("view")
+                .long("view")
+                .help("How the value is shown: digital (segmented digits) or gauge (analog moving-coil meter with a needle)")
+                .required(false)
+                .possible_values(["digital", "gauge"])
+                .default_value("digital"),
+        )
+        .arg(
+            Arg::new("gauge_range")
+                .long("gauge_range")
+                .help("Scale of the gauge view: auto (range of the value), auto_zero (zero in the middle) or fixed <min>,<max> (i.e. 0,300)")
+                .required(false)
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("digits_update")
+                .long("digits_update")
+                .help("Minimum time in miliseconds between changes of the digits, to make them readable on fast meters (bar graph follows every reading)")
+                .required(false)
+                .default_value("0")
+                .validator(Config::validate_number),
+        )
+        .arg(
+            Arg::new("statistics_line")
+                .long("statistics_line")
+                .help("Line with MIN, MAX, AVG, standard deviation, count of readings and time since reset under the value; R key resets statistics, histogram and chart (1: enabled, 0: disabled)")
+                .required(false)
+                .default_value("1"),
+        )
+        .arg(
+            Arg::new("alarm")
+                .long("alarm")
+                .help("Alarm conditions, i.e. high=5.25,low=4.75,rate=0.5,stale (value above high, below low, changing faster than rate per second or stale reading); tripped alarm flashes until acknowledged with the A key, is logged and runs --alarm_command (repeat for each port, the last one is used for remaining ports)")
+                .required(false)
+                .multiple_occurrences(true)
+                .default_value(""),
+        )
+        .arg(
+            Arg::new("alarm_command")
+                .long("alarm_command")
+                .help("Shell command run when an alarm trips, with OMM_CHANNEL, OMM_ALARM and OMM_VALUE environment variables (i.e. to switch off a power supply)")
+                .required(false)
+                .default_value(""),
+        )
+        .arg(
+            Arg::new("alarm_fifo")
+                .long("alarm_fifo")
+                .help("File or named pipe (FIFO) to which a line <timestamp>,<channel>,<alarm>,<value> is written when an alarm trips")
+                .required(false)
+                .default_value(""),
         )
         .arg(
             Arg::new("window_position")
                 .short('w')
                 .long("window_position")
-                .help("Setting up program window position on the screen <x_pos>_<y_pos>, where x_pos and y_pos are in range {1..4} (i.e. 3_3 in the middle of the screen)")
+                .help("Setting up program window position on the screen: <x_pos>_<y_pos>, where x_pos and y_pos are in range {1..4} (i.e. 3_3 in the middle of the screen), <x>,<y> in pixels, anchor (top-left, top, top-right, left, center, right, bottom-left, bottom, bottom-right) or last (position from the last run of the channel)")
                 .takes_value(true)
-                .required(true),
+                .required_unless_present_any(["profile", "setup"]),
+        )
+        .arg(
+            Arg::new("monitor")
+                .long("monitor")
+                .help("Index of the monitor for the window position (default is the current monitor)")
+                .required(false)
+                .default_value(""),
         )
         .arg(
             Arg::new("scpi_protocol_enabled")
@@ -521,23 +1041,69 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .long("scpi_protocol_enabled")
                 .help("Setting up SCPI protocol for reading measurements from all laboratory multimeters (SCPI 'MEAS?' command send and parse response as measurement value; possible scentific representation of value)")
                 .takes_value(true)
-                .required(true),
+                .required_unless_present_any(["profile", "setup"]),
         )
         .arg(
             Arg::new("enable_chart")
                 .short('e')
                 .long("enable_chart")
-                .help("Enable dynamic charts (h: histogram, l: linear) on bottom side of measurement screen.")
+                .help("Enable dynamic charts (h: histogram, l: linear) on bottom side of measurement screen (switched at runtime with H, L and N keys).")
                 .required(false)
                 .default_value(""),
         )
+        .arg(
+            Arg::new("chart_window")
+                .long("chart_window")
+                .help("Time window of the linear chart, scrolling with the present: all or a time like 10s, 1m, 10m, 2h (switched at runtime with the W key)")
+                .required(false)
+                .default_value("1m"),
+        )
+        .arg(
+            Arg::new("chart_y_range")
+                .long("chart_y_range")
+                .help("Value range of the linear chart: auto (range of values in the time window), auto_zero (zero in the middle) or fixed <min>,<max> (i.e. 4.9,5.1)")
+                .required(false)
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("chart_style")
+                .long("chart_style")
+                .help("Drawing of the linear chart: points, line, step, area (line filled down to zero) or envelope (band from the lowest to the highest reading with the average line) (repeat for each port, the last one is used for remaining ports)")
+                .required(false)
+                .multiple_occurrences(true)
+                .possible_values(ChartStyle::NAMES)
+                .default_value("points"),
+        )
+        .arg(
+            Arg::new("chart_average")
+                .long("chart_average")
+                .help("Moving average line over the linear chart with the period as a time like 10s, 1m; empty for none (repeat for each port, the last one is used for remaining ports)")
+                .required(false)
+                .multiple_occurrences(true)
+                .default_value(""),
+        )
         .arg(
             Arg::new("color")
                 .short('c')
                 .long("color")
-                .help("Color of the display values: r for red, g for green, b for blue (default color is red if not specified)")
+                .help("Color of the display values as hex RGB (i.e. FFB000), decimal R,G,B or name (r/red, g/green, b/blue, white, yellow, orange); default is the digits color of the theme")
+                .required(false)
+                .default_value(""),
+        )
+        .arg(
+            Arg::new("theme")
+                .long("theme")
+                .help("Colors of the display and charts: dark, daylight (high-contrast), amber (VFD) or green_lcd")
                 .required(false)
-                .default_value("r"),
+                .possible_values(Theme::NAMES)
+                .default_value("dark"),
+        )
+        .arg(
+            Arg::new("theme_file")
+                .long("theme_file")
+                .help("File with theme colors overriding the ones of --theme, one 'element = color' per line (elements: background, digits, label, chart_points, axes, grid, chart_labels, histogram, zone_pass, zone_warning, zone_fail)")
+                .required(false)
+                .default_value(""),
         )
         .arg(
             Arg::new("enable_csv_logger")
@@ -547,208 +1113,606 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .required(false)
                 .default_value(""),
         )
-        .get_matches();
-
-    let config = Config::new(&matches).unwrap();
+        .arg(
+            Arg::new("stale_timeout")
+                .long("stale_timeout")
+                .help("Time in miliseconds without a valid reading, after which displayed value is marked as stale")
+                .required(false)
+                .default_value(STALE_TIMEOUT_MILISEC_DEFAULT)
+                .validator(Config::validate_number),
+        )
+        .arg(
+            Arg::new("stale_indication")
+                .long("stale_indication")
+                .help("How to show a stale value: dim (dimmed last value) or dashes (value replaced by dashes)")
+                .required(false)
+                .possible_values(["dim", "dashes"])
+                .default_value("dim"),
+        )
+        .arg(
+            Arg::new("display_format")
+                .long("display_format")
+                .help("Format of the displayed value: raw (as received from meter) or eng (engineering notation with SI prefix moved to the unit, i.e. 123.00 µV)")
+                .required(false)
+                .possible_values(["raw", "eng"])
+                .default_value("raw"),
+        )
+        .arg(
+            Arg::new("significant_digits")
+                .long("significant_digits")
+                .help("Number of significant digits shown in engineering notation")
+                .required(false)
+                .default_value("5")
+                .validator(Config::validate_number),
+        )
+        .arg(
+            Arg::new("eng_range")
+                .long("eng_range")
+                .help("SI prefix used in engineering notation: auto or fixed range to avoid prefix flicker (p, n, u, m, base, k, M, G)")
+                .required(false)
+                .possible_values(["auto", "p", "n", "u", "m", "base", "k", "M", "G"])
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::new("window_size")
+                .long("window_size")
+                .help("Initial window size <width>x<height> in pixels (i.e. 1800x300); window can be resized and the display is scaled to it")
+                .required(false)
+                .default_value(""),
+        )
+        .arg(
+            Arg::new("always_on_top")
+                .long("always_on_top")
+                .help("Keep the window above all other windows (1: enabled, 0: disabled)")
+                .required(false)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("borderless")
+                .long("borderless")
+                .help("Window without title bar and borders, moved by dragging the value with the mouse (1: enabled, 0: disabled)")
+                .required(false)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("background")
+                .long("background")
+                .help("Background color as hex RGB (i.e. 00FF00 for chroma-key) or transparent (default is the background of the theme)")
+                .required(false)
+                .default_value(""),
+        )
+        .arg(
+            Arg::new("kiosk")
+                .long("kiosk")
+                .help("Fullscreen kiosk mode for bench displays with a touchscreen: channels fill the screen, no mouse cursor, touch buttons for hold, rel, reset, pause and chart, reconnects lost serial ports (1: enabled, 0: disabled)")
+                .required(false)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::new("renderer")
+                .long("renderer")
+                .help("How digits are drawn: font (7-segment font), seg7 or seg14 (vector 7 or 14 segments with unlit segments visible like on LCD)")
+                .required(false)
+                .possible_values(["font", "seg7", "seg14"])
+                .default_value("font"),
+        )
+        .arg(
+            Arg::new("resolution")
+                .long("resolution")
+                .help("Display resolution like in real meters: digits (i.e. 3.5 to 8.5 or 6) or counts (i.e. 6000, 50000); value is rounded to it (not used by default; repeat for each port, the last one is used for remaining ports)")
+                .required(false)
+                .multiple_occurrences(true)
+                .default_value(""),
+        )
+        .arg(
+            Arg::new("setup")
+                .long("setup")
+                .help("Named setup from the config file open-modern-multimeter.toml in the current directory or ~/.open-modern-multimeter/config.toml (i.e. bench-a); options given on the command line override it")
+                .takes_value(true)
+                .required(false),
+        )
+        .arg(
+            Arg::new("profile")
+                .long("profile")
+                .help("Settings saved with the settings panel (S key) under the name, in ~/.open-modern-multimeter/profiles/<name>.conf; options given on the command line override them")
+                .takes_value(true)
+                .required(false),
+        )
+}
 
-    let port = serialport::new(&config.port_name, config.baud_rate)
-        .timeout(Duration::from_millis(
-            SERIAL_TIMEOUT_MILISEC.try_into().unwrap(),
-        ))
-        .open();
+/// Command line completed with options of the profile and the setup: options given on
+/// the command line win over the profile, which wins over the setup.
+fn merged_args(
+    matches: &clap::ArgMatches,
+    profile: &str,
+    setup: &str,
+) -> Result<Vec<String>, String> {
+    let mut sources = vec![];
+    if !profile.is_empty() {
+        sources.push((format!("profile '{}'", profile), load_profile(profile)?));
+    }
+    if !setup.is_empty() {
+        sources.push((format!("setup '{}'", setup), load_setup(setup)?));
+    }
+    merge_options(&command(), matches, &sources)
+}
 
-    let mut screen_height_size = SCREEN_HEIGHT;
-    if &config.enable_chart != "" {
-        screen_height_size = SCREEN_HEIGHT * 2;
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let now = Utc::now().timestamp_millis();
+    let matches = command().get_matches();
+    let profile = matches.value_of("profile").unwrap_or_default().to_string();
+    let setup = matches.value_of("setup").unwrap_or_default();
+    let mut matches = if profile.is_empty() && setup.is_empty() {
+        matches
+    } else {
+        let args = merged_args(&matches, &profile, setup).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        });
+        command().get_matches_from(args)
+    };
+
+    let mut config = Config::new(&matches).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+
+    let mut channels: Vec<Channel> = config
+        .channels
+        .iter()
+        .enumerate()
+        .map(|(i, channel)| {
+            Channel::new(
+                channel.clone(),
+                &config.enable_chart,
+                config.chart_views[i],
+                config.stale_timeout,
+            )
+        })
+        .collect();
+    for channel in channels.iter_mut() {
+        if let Err(e) = channel.open() {
+            eprintln!("{}", e);
+            // Kiosk keeps running and reconnects, i.e. when the meter is switched on later
+            if !config.kiosk {
+                std::process::exit(1);
+            }
+        }
     }
 
-    let (mut rl, thread) = raylib::init()
-        .size(SCREEN_WIDTH, screen_height_size)
-        .title(&APP_NAME)
-        .vsync()
-        .build();
-
-    let (max_screen_width, max_screen_height) = get_screen_resolution();
-    let (window_pos_x, window_pos_y) = calculate_window_position(
-        &config.window_position,
-        max_screen_width,
-        max_screen_height,
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-    );
+    let screen_height_size = Layout::total_height(&channels);
 
-    unsafe {
-        raylib::ffi::SetWindowPosition(window_pos_x, window_pos_y);
+    let mut builder = raylib::init();
+    builder
+        .size(SCREEN_WIDTH, screen_height_size)
+        .title(APP_NAME)
+        .resizable()
+        .msaa_4x()
+        .vsync();
+    if config.borderless || config.kiosk {
+        builder.undecorated();
     }
+    if config.background.is_none() {
+        builder.transparent();
+    }
+    let (mut rl, thread) = builder.build();
+    set_up_window(&mut rl, &config, screen_height_size).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
 
     rl.set_target_fps(60);
 
     let font_file: &[u8] = include_bytes!("./7_Segment.ttf");
-    let display = Display::new(font_file);
-
-    // Adjust min, max, and bin_count as needed
-    let mut histogram = Histogram::new(0.0, 10.0, 50);
-
-    let mut data_points = vec![];
-    
-    let csv_logger_file_name = format!("measurements_{}_{}.csv", now, config.channel_no);
-
-    match port {
-        Ok(mut port) => {
-            //let mut serial_buf: Vec<u8> = vec![0; SERIAL_BUFFER_SIZE.try_into().unwrap()];
-            let mut ts: f32 = 0.0;
-            while !rl.window_should_close() {
-                let mut serial_buf: Vec<u8> = vec![0; SERIAL_BUFFER_SIZE.try_into().unwrap()];
-                //serial_buf = [0; 0].to_vec();
-                ts += 1.0;
-                if config.scpi_protocol_enabled == true {
-                    match port.write(SCPI_MEAS_CMD_OWON) {
-                        Ok(_) => {}
-                        Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => (),
+    let mut display = Display::new(font_file, config.segment_style, config.theme.label);
+
+    let csv_logger_file_name =
+        |channel: &Channel| format!("measurements_{}_{}.csv", now, channel.config.channel_no);
+    let mut alarm_hook = AlarmHook::new(config.alarm_command.clone(), config.alarm_fifo.clone());
+    let mut selected_channel = 0;
+    let mut window_drag = WindowDrag::new();
+    let mut settings: Option<SettingsPanel> = None;
+    while !rl.window_should_close() {
+        let mut new_measurements = vec![];
+        for channel in channels.iter_mut() {
+            let new_measurement = channel.poll(config.scpi_protocol_enabled);
+            let stale = channel.health.is_stale() && !channel.is_paused();
+            for kind in channel.alarm.check(new_measurement.as_ref(), stale) {
+                let value = channel
+                    .measurement
+                    .as_ref()
+                    .filter(|m| m.is_valid())
+                    .map(|m| m.value);
+                eprintln!("ALARM {} on CH:{}", kind.name(), channel.config.channel_no);
+                alarm_hook.fire(channel.config.channel_no, kind, value);
+                if config.enable_csv_logger {
+                    append_alarm_to_csv(&csv_logger_file_name(channel), kind, value)?;
+                }
+            }
+            new_measurements.push(new_measurement);
+        }
+        alarm_hook.reap();
+
+        let rows_height = Layout::total_height(&channels);
+        match settings.as_mut().and_then(|panel| panel.update(&mut rl)) {
+            Some(SettingsEvent::Apply(args)) => {
+                let applied = command()
+                    .try_get_matches_from(&args)
+                    .map_err(|e| e.to_string())
+                    .and_then(|new_matches| Ok((Config::new(&new_matches)?, new_matches)));
+                let message = match applied {
+                    Ok((new_config, new_matches)) => {
+                        reconfigure_channels(&mut channels, &config, &new_config);
+                        // Readings of replaced channels are not logged to files of new ones
+                        new_measurements.clear();
+                        selected_channel = selected_channel.min(channels.len() - 1);
+                        display = Display::new(
+                            font_file,
+                            new_config.segment_style,
+                            new_config.theme.label,
+                        );
+                        alarm_hook.configure(
+                            new_config.alarm_command.clone(),
+                            new_config.alarm_fifo.clone(),
+                        );
+                        let window_changed = config.window_changed(&new_config);
+                        config = new_config;
+                        matches = new_matches;
+                        if window_changed {
+                            set_up_window(&mut rl, &config, Layout::total_height(&channels))
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Some(panel) = settings.as_mut() {
+                    match message {
+                        Ok(()) => panel.show_message("Settings applied", false),
+                        // Command line errors without the usage
                         Err(e) => {
-                            eprintln!("Error writing data to port (after SCPI cmd.): {:?}", e)
+                            let lines: Vec<&str> =
+                                e.lines().take_while(|line| !line.is_empty()).collect();
+                            panel.show_message(&lines.join(" "), true)
                         }
                     }
                 }
-
-                let mut bytes_read_val = 0;
-                match port.read(serial_buf.as_mut_slice()) {
-                    Ok(bytes_read) => {
-                        bytes_read_val = bytes_read;
-                        io::stdout().write_all(&serial_buf[..bytes_read]).unwrap();
-                        io::stdout().flush().unwrap();
+            }
+            Some(SettingsEvent::Close) => settings = None,
+            None if settings.is_none() => {
+                for (key, action) in HOTKEYS {
+                    if rl.is_key_pressed(key) {
+                        for channel in channels.iter_mut() {
+                            channel.apply(action);
+                        }
                     }
-                    Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => (),
-                    Err(e) => eprintln!("Error reading data from port: {:?}", e),
                 }
-
-                fn extract_first_part(input: &[u8]) -> Vec<u8> {
-                    if let Some(position) = input.windows(2).position(|window| window == b"\n") {
-                        input[..position].to_vec()
-                    } else {
-                        input.to_vec() // Return the full buffer if no "\r\n" is found
-                    }
+                if rl.is_key_pressed(KeyboardKey::KEY_S) {
+                    settings = Some(SettingsPanel::new(&command(), &matches, &profile));
                 }
-
-                //serial_buf = (&serial_buf[..bytes_read_val]).to_vec();
-                //if (serial_buf.is_empty()) {
-                //  continue;
-                //}
-                //serial_buf = b"0.123E-03\r\n".to_vec();
-                //serial_buf = b"1.011038E-01\n".to_vec();
-                //serial_buf = b"1.013807E-01\r\n1.013807E-01\r\n3807E-01\r\n1.013807E-01\r\n\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0".to_vec();
-                //println!("serial_buf: {:#?}", join2(&serial_buf));
-                //serial_buf = extract_first_part(&serial_buf);
-                //println!("serial_buf: {:#?}", serial_buf);
-
-                let mut serial_buf_filtered = serial_buf.clone();
-                serial_buf_filtered.retain(|&x| x != 0);
-
-                //let serial_buf_filtered = convert_scientific_to_float(&serial_buf_filtered);
-
-                //println!("TO: {:?}", join(&convert_scientific_to_float(&serial_buf_filtered)));
-
-                // Convert scientific value
-                match convert_scientific_to_float2(&serial_buf_filtered) {
-                    Ok(result) => {
-                        //println!("TO: {}", result);
-                        serial_buf_filtered = result.into();
-                    }
-                    Err(e) => {
-                        eprintln!("Error: {:?}", e);
-                        continue;
+            }
+            None => {}
+        }
+        // Nothing on the bench should close the readout by accident, in the settings panel
+        // Esc closes only the panel
+        rl.set_exit_key(if config.kiosk || settings.is_some() {
+            None
+        } else {
+            Some(KeyboardKey::KEY_ESCAPE)
+        });
+
+        let (screen_width, screen_height) = (rl.get_screen_width(), rl.get_screen_height());
+        let button_bar = if config.kiosk {
+            ButtonBar::new(screen_width, screen_height)
+        } else {
+            ButtonBar::overlay(screen_width, screen_height)
+        };
+        let buttons_shown = (config.kiosk || rl.is_cursor_on_screen()) && settings.is_none();
+        let channels_height = if config.kiosk {
+            button_bar.top()
+        } else {
+            screen_height as f32
+        };
+        let layouts = Layout::rows(
+            Rectangle::new(0.0, 0.0, screen_width as f32, channels_height),
+            &channels,
+        );
+        let mut button_pressed = false;
+        if buttons_shown && rl.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) {
+            let touch = rl.get_mouse_position();
+            if let Some(action) = button_bar.action_at(touch) {
+                match action {
+                    ButtonAction::Channel(action) => channels[selected_channel].apply(action),
+                    ButtonAction::Settings => {
+                        settings = Some(SettingsPanel::new(&command(), &matches, &profile))
                     }
                 }
-
-                if serial_buf_filtered.len() > 8 {
-                    serial_buf_filtered.truncate(9);
+                button_pressed = true;
+            } else if let Some(row) = layouts
+                .iter()
+                .position(|layout| layout.area.check_collision_point_rec(touch))
+            {
+                selected_channel = row;
+            }
+        }
+        if config.borderless && !config.kiosk && !button_pressed && settings.is_none() {
+            window_drag.update(&mut rl, &layouts);
+        }
+        if !button_pressed && settings.is_none() {
+            for (channel, layout) in channels.iter_mut().zip(layouts.iter()) {
+                if &channel.chart == "l" {
+                    let now = channel.chart_time();
+                    channel.chart_navigation.update(
+                        &rl,
+                        layout.plot(),
+                        &channel.chart_view,
+                        &channel.history,
+                        now,
+                    );
                 }
+            }
+        }
+        // Window grows or shrinks with the chart switched on or off at runtime
+        if !config.kiosk && Layout::total_height(&channels) != rows_height {
+            let height = Layout::total_height(&channels) * screen_width / SCREEN_WIDTH;
+            rl.set_window_size(screen_width, height.max(WINDOW_MIN_HEIGHT));
+        }
 
-                let value = match CString::new(serial_buf_filtered) {
-                    Ok(cstr) => match cstr.into_string() {
-                        Ok(string) => string,
-                        Err(_) => "Data From Serial Port Conversion Error".to_string(),
-                    },
-                    Err(_) => "Invalid Data From Serial Port".to_string(),
-                };
-                let mut value_as_f32: f32 = 0.0;
-                match value.parse::<f32>() {
-                    Ok(value_as_f32_tmp) => {
-                        value_as_f32 = value_as_f32_tmp;
-                        histogram.add_value(value_as_f32);
-                        data_points.push((ts, value_as_f32));
-                    }
-                    Err(e) => {
-                        eprintln!("Error parsing string to f32: {}", e);
-                    }
-                }
-                let mut d = rl.begin_drawing(&thread);
-                d.clear_background(DISPLAY_BACKGROUND_COLOR);
-                display.draw(
-                    &mut d,
-                    config.channel_no,
-                    &value,
-                    &config.unit,
-                    &config.color,
+        let frame_time = rl.get_frame_time();
+        for channel in channels.iter_mut() {
+            channel.animate(frame_time);
+        }
+        let alarm_flash_on = (rl.get_time() / ALARM_FLASH_PERIOD_SEC) as i64 % 2 == 0;
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(config.background.unwrap_or(Color::BLANK));
+        for (i, (channel, layout)) in channels.iter().zip(layouts.iter()).enumerate() {
+            if buttons_shown && channels.len() > 1 && i == selected_channel {
+                d.draw_rectangle_lines_ex(
+                    layout.area,
+                    layout.px(2.0) as i32,
+                    DISPLAY_SELECTED_COLOR,
                 );
+            }
+            draw_channel(&mut d, &display, layout, channel, &config, alarm_flash_on);
+        }
+        if buttons_shown {
+            button_bar.draw(&mut d, &channels[selected_channel]);
+        }
+        if let Some(panel) = settings.as_ref() {
+            panel.draw(&mut d);
+        }
 
-                if config.enable_csv_logger && value_as_f32 != 0.0 {
-                  let now = Utc::now().timestamp_millis();
-                  append_to_csv(&csv_logger_file_name, now, value_as_f32)?;
+        for (channel, new_measurement) in channels.iter().zip(new_measurements.iter()) {
+            if let Some(m) = new_measurement.as_ref() {
+                if config.enable_csv_logger && m.value != 0.0 {
+                    append_to_csv(&csv_logger_file_name(channel), m)?;
                 }
+            }
+        }
+    }
 
-                if &config.enable_chart == "h" {
-                    render_histogram(
-                        &mut d,
-                        &histogram,
-                        (SCREEN_WIDTH / 2) as i32 - 50,
-                        -30,
-                        SCREEN_WIDTH,
-                        screen_height_size,
-                        0.3,
-                        Color::DARKGRAY,
-                    );
-                }
+    if !config.kiosk {
+        let window_position = rl.get_window_position();
+        if let Err(e) = save_last_position(
+            config.channels[0].channel_no,
+            (window_position.x as i32, window_position.y as i32),
+        ) {
+            eprintln!("Failed to save window position: {}", e);
+        }
+    }
+    Ok(())
+}
 
-                if &config.enable_chart == "l" {
-                    draw_chart(
-                        &mut d,
-                        Vector2::new(40.0, 150.0),
-                        SCREEN_WIDTH,
-                        100,
-                        "T [ms]",
-                        "V",
-                        &data_points,
-                        2.0,
-                        50.0,
-                        Color::RED,
-                        Color::GRAY,
-                        Color::DARKGRAY,
-                        Color::GRAY,
-                    );
-                }
+/// Places the window on the monitor (fullscreen in kiosk mode), on start and when window
+/// settings are changed.
+fn set_up_window(
+    rl: &mut RaylibHandle,
+    config: &Config,
+    screen_height_size: i32,
+) -> Result<(), String> {
+    let topmost = WindowState::default().set_window_topmost(true);
+    if config.always_on_top {
+        rl.set_window_state(topmost);
+    } else {
+        rl.clear_window_state(topmost);
+    }
+    let undecorated = WindowState::default().set_window_undecorated(true);
+    if config.borderless || config.kiosk {
+        rl.set_window_state(undecorated);
+    } else {
+        rl.clear_window_state(undecorated);
+    }
 
-                /*
-                                //TODO: NEED FIX: below refactoring have blinking effect on screen, so it's known bug ... waiting for hero! ;-)
-                                match read_serial_data(&mut *port, &mut serial_buf) {
-                                    Ok(value) => {
-                                        display.draw(&mut rl, &thread, config.channel_no, &value, &config.unit, &config.color);
-                                    }
-                                    Err(e) => {
-                                        eprintln!("{}", e);
-                                    }
-                                }
-                */
-            }
+    let monitor_area = get_monitor_area(config.monitor)?;
+    if config.kiosk {
+        let (monitor_x, monitor_y, monitor_width, monitor_height) = monitor_area;
+        unsafe {
+            raylib::ffi::SetWindowPosition(monitor_x, monitor_y);
+        }
+        rl.set_window_size(monitor_width, monitor_height);
+        if !rl.is_window_fullscreen() {
+            rl.toggle_fullscreen();
         }
-        Err(e) => {
-            eprintln!("Failed to open serial port \"{}\": {}", config.port_name, e);
-            std::process::exit(1);
+        rl.hide_cursor();
+    } else {
+        if rl.is_window_fullscreen() {
+            rl.toggle_fullscreen();
+        }
+        rl.show_cursor();
+        // On HiDPI screens default window size is scaled, so the meter has the same physical size
+        let (window_width, window_height) = config.window_size.unwrap_or_else(|| {
+            let dpi_scale = rl.get_window_scale_dpi();
+            (
+                (SCREEN_WIDTH as f32 * dpi_scale.x) as i32,
+                (screen_height_size as f32 * dpi_scale.y) as i32,
+            )
+        });
+        rl.set_window_size(window_width, window_height);
+        rl.set_window_min_size(WINDOW_MIN_WIDTH, WINDOW_MIN_HEIGHT);
+
+        let (window_pos_x, window_pos_y) = config.window_position.calculate(
+            monitor_area,
+            window_width,
+            window_height,
+            load_last_position(config.channels[0].channel_no),
+        );
+
+        unsafe {
+            raylib::ffi::SetWindowPosition(window_pos_x, window_pos_y);
         }
     }
     Ok(())
 }
 
+/// Replaces channels whose configuration changed (re-opening their ports); unchanged ones keep
+/// the connection and collected data.
+fn reconfigure_channels(channels: &mut Vec<Channel>, old_config: &Config, config: &Config) {
+    let mut kept: Vec<Option<Channel>> = channels
+        .drain(..)
+        .enumerate()
+        .map(|(i, channel)| {
+            Some(channel).filter(|channel| config.channels.get(i) == Some(&channel.config))
+        })
+        .collect();
+    // Ports of replaced channels are closed by now, so they can be opened again
+    for (i, channel_config) in config.channels.iter().enumerate() {
+        let mut channel = match kept.get_mut(i).and_then(Option::take) {
+            Some(channel) => channel,
+            None => {
+                let mut channel = Channel::new(
+                    channel_config.clone(),
+                    &config.enable_chart,
+                    config.chart_views[i],
+                    config.stale_timeout,
+                );
+                if let Err(e) = channel.open() {
+                    eprintln!("{}", e);
+                }
+                channel
+            }
+        };
+        channel.health.stale_timeout = config.stale_timeout;
+        if config.enable_chart != old_config.enable_chart {
+            channel.chart = config.enable_chart.clone();
+        }
+        if config.chart_views.get(i) != old_config.chart_views.get(i) {
+            channel.chart_view = config.chart_views[i];
+        }
+        channels.push(channel);
+    }
+}
+
+/// Draws the reading of one channel with its chart, in the area of the layout.
+fn draw_channel(
+    d: &mut RaylibDrawHandle<'_>,
+    display: &Display,
+    layout: &Layout,
+    channel: &Channel,
+    config: &Config,
+    alarm_flash_on: bool,
+) {
+    let measurement = channel.shown_measurement();
+    let (value, unit) = match measurement.as_ref() {
+        Some(m) => format_measurement(m, &config.display_format, channel.config.resolution),
+        None => (String::new(), channel.config.unit.clone()),
+    };
+    let flags = measurement.as_ref().map(|m| m.flags).unwrap_or_default();
+    // Gauge scale is in the unit of the meter, not the one chosen by the display format
+    let unit = match channel.gauge {
+        Some(_) => channel.config.unit.clone(),
+        None => unit,
+    };
+    let digits_color = if channel.alarm.is_latched() {
+        // Latched alarm flashes until acknowledged
+        if alarm_flash_on {
+            config.theme.zone_fail
+        } else {
+            config.theme.zone_fail.fade(ALARM_DIM_ALPHA)
+        }
+    } else {
+        channel
+            .zone()
+            .map_or(config.theme.digits, |zone| config.theme.zone_color(zone))
+    };
+    // Held or paused value is frozen on purpose, so it is never shown as stale
+    let stale = channel.health.is_stale() && !channel.is_held() && !channel.is_paused();
+    let (shown_value, shown_color) = if !stale {
+        (value.as_str(), digits_color)
+    } else if config.stale_dashes {
+        (STALE_DASHES, digits_color)
+    } else {
+        (value.as_str(), digits_color.fade(STALE_DIM_ALPHA))
+    };
+    display.draw(
+        d,
+        layout,
+        channel.config.channel_no,
+        &Readout {
+            value: shown_value,
+            unit: &unit,
+            flags,
+            color: shown_color,
+            bargraph: channel.bargraph.as_ref(),
+            gauge: channel.gauge.as_ref(),
+        },
+    );
+    display.draw_health(
+        d,
+        layout,
+        &channel.health,
+        channel.is_paused(),
+        channel.alarm.latched(),
+    );
+    if config.statistics_line {
+        display.draw_statistics(d, layout, &channel.statistics, &channel.config.unit);
+    }
+    if channel.alarm.is_latched() && alarm_flash_on {
+        d.draw_rectangle_lines_ex(
+            layout.display(),
+            layout.px(3.0) as i32,
+            config.theme.zone_fail,
+        );
+    }
+
+    let chart = layout.chart();
+    if &channel.chart == "h" {
+        render_histogram(
+            d,
+            &channel.histogram,
+            Rectangle::new(
+                chart.x + chart.width / 2.0 - layout.px(50.0),
+                chart.y,
+                chart.width.floor(),
+                (chart.height - layout.px(30.0)).floor(),
+            ),
+            0.3,
+            config.theme.histogram,
+        );
+    }
+
+    if &channel.chart == "l" {
+        let point_color = |value: f64| match channel.config.limits {
+            Some(limits) => config.theme.zone_color(limits.zone_of(value)),
+            None => config.theme.chart_points,
+        };
+        draw_chart(
+            d,
+            &ChartLayout {
+                area: layout.plot(),
+                point_circle_size: layout.px(2.0),
+                grid_step: layout.px(50.0),
+                point_color: &point_color,
+                theme: &config.theme,
+            },
+            &channel.config.unit.to_string(),
+            &channel.history,
+            &channel.chart_view,
+            &channel.chart_navigation,
+            channel.chart_time(),
+        );
+    }
+}
+
 /*
 
 // working example of scientific to f32 conversion
@@ -0,0 +1,343 @@
+use chrono::prelude::*;
+use std::fmt;
+
+const SCPI_OVERLOAD_VALUE: f64 = 9.9E37;
+const SCPI_NAN_VALUE: f64 = 9.91E37;
+const SCPI_SPECIAL_VALUE_TOLERANCE: f64 = 5.0E34; // Half the distance of the two values
+
+/// SI prefix of the unit in which the meter reports values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SiPrefix {
+    Pico,
+    Nano,
+    Micro,
+    Milli,
+    None,
+    Kilo,
+    Mega,
+    Giga,
+}
+
+impl SiPrefix {
+    /// All prefixes ordered from the smallest to the largest.
+    pub const ALL: [SiPrefix; 8] = [
+        SiPrefix::Pico,
+        SiPrefix::Nano,
+        SiPrefix::Micro,
+        SiPrefix::Milli,
+        SiPrefix::None,
+        SiPrefix::Kilo,
+        SiPrefix::Mega,
+        SiPrefix::Giga,
+    ];
+
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "p" => Some(SiPrefix::Pico),
+            "n" => Some(SiPrefix::Nano),
+            "u" | "µ" => Some(SiPrefix::Micro),
+            "m" => Some(SiPrefix::Milli),
+            "" => Some(SiPrefix::None),
+            "k" | "K" => Some(SiPrefix::Kilo),
+            "M" => Some(SiPrefix::Mega),
+            "G" => Some(SiPrefix::Giga),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            SiPrefix::Pico => "p",
+            SiPrefix::Nano => "n",
+            SiPrefix::Micro => "µ",
+            SiPrefix::Milli => "m",
+            SiPrefix::None => "",
+            SiPrefix::Kilo => "k",
+            SiPrefix::Mega => "M",
+            SiPrefix::Giga => "G",
+        }
+    }
+
+    /// Power of ten represented by the prefix.
+    pub fn exponent(&self) -> i32 {
+        match self {
+            SiPrefix::Pico => -12,
+            SiPrefix::Nano => -9,
+            SiPrefix::Micro => -6,
+            SiPrefix::Milli => -3,
+            SiPrefix::None => 0,
+            SiPrefix::Kilo => 3,
+            SiPrefix::Mega => 6,
+            SiPrefix::Giga => 9,
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        10f64.powi(self.exponent())
+    }
+}
+
+/// Physical quantity measured by the meter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Quantity {
+    Voltage,
+    Current,
+    Resistance,
+    Capacitance,
+    Frequency,
+    Power,
+    Other(String), // Free-form unit, displayed as given
+}
+
+impl Quantity {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "V" => Some(Quantity::Voltage),
+            "A" => Some(Quantity::Current),
+            "Ohm" | "OHM" | "ohm" | "Ω" => Some(Quantity::Resistance),
+            "F" => Some(Quantity::Capacitance),
+            "Hz" | "HZ" => Some(Quantity::Frequency),
+            "W" => Some(Quantity::Power),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            Quantity::Voltage => "V",
+            Quantity::Current => "A",
+            Quantity::Resistance => "Ohm",
+            Quantity::Capacitance => "F",
+            Quantity::Frequency => "Hz",
+            Quantity::Power => "W",
+            Quantity::Other(unit) => unit,
+        }
+    }
+}
+
+/// AC/DC coupling of the measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coupling {
+    None,
+    Dc,
+    Ac,
+}
+
+impl Coupling {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Coupling::None => "",
+            Coupling::Dc => "DC",
+            Coupling::Ac => "AC",
+        }
+    }
+}
+
+/// Unit of measurement parsed from free-form text like `VDC`, `mVAC`, `kOhm` or `Hz`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub prefix: SiPrefix,
+    pub quantity: Quantity,
+    pub coupling: Coupling,
+}
+
+impl Unit {
+    /// Never fails - a unit which can not be recognized is kept as `Quantity::Other`.
+    pub fn parse(text: &str) -> Self {
+        let text = text.trim();
+        let (rest, coupling) = if let Some(rest) = text.strip_suffix("DC") {
+            (rest, Coupling::Dc)
+        } else if let Some(rest) = text.strip_suffix("AC") {
+            (rest, Coupling::Ac)
+        } else {
+            (text, Coupling::None)
+        };
+        let rest = rest.trim_end_matches([' ', '_']);
+
+        for (split, _) in rest.char_indices() {
+            let (prefix, quantity) = rest.split_at(split);
+            if let (Some(prefix), Some(quantity)) = (
+                SiPrefix::from_symbol(prefix),
+                Quantity::from_symbol(quantity),
+            ) {
+                return Unit {
+                    prefix,
+                    quantity,
+                    coupling,
+                };
+            }
+        }
+        Unit {
+            prefix: SiPrefix::None,
+            quantity: Quantity::Other(text.to_string()),
+            coupling: Coupling::None,
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.prefix.symbol(),
+            self.quantity.symbol(),
+            self.coupling.suffix()
+        )
+    }
+}
+
+/// State of a single reading reported by the meter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadingState {
+    Valid,
+    OverloadPositive,
+    OverloadNegative,
+    Underrange,
+    Invalid,
+}
+
+impl ReadingState {
+    /// Recognizes textual overload/underrange markers sent by handheld meters (`OL`, `-OL`, `UL`)
+    /// and SCPI special values (`9.9E37` for overload, `9.91E37` for NaN).
+    fn classify(text: &str, number: Option<f64>) -> Option<Self> {
+        match text.to_ascii_uppercase().as_str() {
+            "OL" | "+OL" | "OVLD" | "OVERLOAD" => return Some(ReadingState::OverloadPositive),
+            "-OL" | "-OVLD" => return Some(ReadingState::OverloadNegative),
+            "UL" | "UNDER" | "UNDERRANGE" => return Some(ReadingState::Underrange),
+            "NAN" | "+NAN" | "-NAN" => return Some(ReadingState::Invalid),
+            _ => {}
+        }
+        let number = number?;
+        if number.is_nan() || (number.abs() - SCPI_NAN_VALUE).abs() < SCPI_SPECIAL_VALUE_TOLERANCE {
+            Some(ReadingState::Invalid)
+        } else if number.is_infinite() || number.abs() >= SCPI_OVERLOAD_VALUE {
+            if number > 0.0 {
+                Some(ReadingState::OverloadPositive)
+            } else {
+                Some(ReadingState::OverloadNegative)
+            }
+        } else {
+            Some(ReadingState::Valid)
+        }
+    }
+
+    pub fn is_overload(&self) -> bool {
+        matches!(
+            self,
+            ReadingState::OverloadPositive | ReadingState::OverloadNegative
+        )
+    }
+
+    /// Text shown on the 7-segment display instead of a value.
+    pub fn display_text(&self) -> &'static str {
+        match self {
+            ReadingState::Valid => "",
+            ReadingState::OverloadPositive => "OL",
+            ReadingState::OverloadNegative => "-OL",
+            ReadingState::Underrange => "UL",
+            ReadingState::Invalid => "----",
+        }
+    }
+
+    /// Flag written to the CSV log next to the value.
+    pub fn log_flag(&self) -> &'static str {
+        match self {
+            ReadingState::Valid => "OK",
+            ReadingState::OverloadPositive => "OL",
+            ReadingState::OverloadNegative => "-OL",
+            ReadingState::Underrange => "UL",
+            ReadingState::Invalid => "INVALID",
+        }
+    }
+}
+
+/// Status flags shown next to a reading: overload from the reading itself, HOLD and REL from
+/// the channel presenting it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeasurementFlags {
+    pub hold: bool,
+    pub rel: bool,
+    pub overload: bool,
+}
+
+/// Single measurement received from the meter.
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub value: f64, // Value in `unit`, NaN when `state` is not valid
+    pub timestamp: DateTime<Utc>,
+    pub unit: Unit,
+    pub state: ReadingState,
+    pub flags: MeasurementFlags,
+}
+
+impl Measurement {
+    /// Converts one complete line received from the meter into a measurement.
+    pub fn parse(line: &[u8], unit: &Unit) -> Result<Self, String> {
+        let line =
+            String::from_utf8(line.to_vec()).map_err(|e| format!("Invalid UTF-8 data: {}", e))?;
+        let line = line.trim();
+        let number = line.parse::<f64>().ok();
+        let state = ReadingState::classify(line, number)
+            .ok_or_else(|| format!("Failed to parse number: {:?}", line))?;
+        let value = match state {
+            ReadingState::Valid => number.unwrap_or(f64::NAN),
+            _ => f64::NAN,
+        };
+
+        Ok(Measurement {
+            value,
+            timestamp: Utc::now(),
+            unit: unit.clone(),
+            state,
+            flags: MeasurementFlags {
+                overload: state.is_overload(),
+                ..MeasurementFlags::default()
+            },
+        })
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.state == ReadingState::Valid
+    }
+
+    /// Value converted to the base SI unit (i.e. `mV` reading to `V`).
+    pub fn si_value(&self) -> f64 {
+        self.value * self.unit.prefix.factor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(text: &str) -> Option<ReadingState> {
+        ReadingState::classify(text, text.parse::<f64>().ok())
+    }
+
+    #[test]
+    fn classifies_scpi_special_values() {
+        assert_eq!(classify("9.9E37"), Some(ReadingState::OverloadPositive));
+        assert_eq!(classify("-9.9E37"), Some(ReadingState::OverloadNegative));
+        assert_eq!(classify("9.91E37"), Some(ReadingState::Invalid));
+        assert_eq!(
+            classify("+9.90000000E+37"),
+            Some(ReadingState::OverloadPositive)
+        );
+    }
+
+    #[test]
+    fn classifies_text_markers() {
+        assert_eq!(classify("OL"), Some(ReadingState::OverloadPositive));
+        assert_eq!(classify("-ol"), Some(ReadingState::OverloadNegative));
+        assert_eq!(classify("UL"), Some(ReadingState::Underrange));
+        assert_eq!(classify("NaN"), Some(ReadingState::Invalid));
+        assert_eq!(classify("abc"), None);
+    }
+
+    #[test]
+    fn classifies_normal_value_as_valid() {
+        assert_eq!(classify("1.2345E+00"), Some(ReadingState::Valid));
+        assert_eq!(classify("-0.5"), Some(ReadingState::Valid));
+    }
+}
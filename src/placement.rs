@@ -0,0 +1,261 @@
+use std::fs;
+use std::path::PathBuf;
+
+const GRID_SECTIONS: i32 = 4;
+const POSITIONS_DIR: &str = ".open-modern-multimeter";
+const POSITIONS_FILE: &str = "window_positions.csv";
+
+/// Where the program window is placed on the monitor.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowPlacement {
+    /// Centered in one of 4x4 screen sections `X_Y`, both in range 1..4.
+    Grid(i32, i32),
+    /// Top left window corner in pixels `X,Y`, relative to the monitor; negative coordinates
+    /// are kept (they reach monitors left of or above the chosen one).
+    Absolute(i32, i32),
+    /// Window aligned to monitor edges, i.e. `top-right` or `center`.
+    Anchor(Anchor),
+    /// Position remembered from the last run of the same channel.
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "top-left" => Some(Anchor::TopLeft),
+            "top" => Some(Anchor::Top),
+            "top-right" => Some(Anchor::TopRight),
+            "left" => Some(Anchor::Left),
+            "center" => Some(Anchor::Center),
+            "right" => Some(Anchor::Right),
+            "bottom-left" => Some(Anchor::BottomLeft),
+            "bottom" => Some(Anchor::Bottom),
+            "bottom-right" => Some(Anchor::BottomRight),
+            _ => None,
+        }
+    }
+
+    /// Horizontal and vertical alignment: 0 - start, 1 - center, 2 - end.
+    fn alignment(&self) -> (i32, i32) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+impl WindowPlacement {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text == "last" {
+            return Ok(WindowPlacement::Last);
+        }
+        if let Some(anchor) = Anchor::parse(text) {
+            return Ok(WindowPlacement::Anchor(anchor));
+        }
+        if let Some((x, y)) = text.split_once(',') {
+            let invalid = || format!("Invalid window position '{}', expected pixels 'X,Y'", text);
+            let x = x.trim().parse::<i32>().map_err(|_| invalid())?;
+            let y = y.trim().parse::<i32>().map_err(|_| invalid())?;
+            return Ok(WindowPlacement::Absolute(x, y));
+        }
+        if let Some((x, y)) = text.split_once('_') {
+            let invalid = || {
+                format!(
+                    "Invalid window position '{}', expected 'X_Y' with sections in range 1..{}",
+                    text, GRID_SECTIONS
+                )
+            };
+            let x = x.parse::<i32>().map_err(|_| invalid())?;
+            let y = y.parse::<i32>().map_err(|_| invalid())?;
+            if !(1..=GRID_SECTIONS).contains(&x) || !(1..=GRID_SECTIONS).contains(&y) {
+                return Err(invalid());
+            }
+            return Ok(WindowPlacement::Grid(x, y));
+        }
+        Err(format!(
+            "Invalid window position '{}', expected 'X_Y', 'X,Y', anchor (i.e. top-right) or 'last'",
+            text
+        ))
+    }
+
+    /// Calculates top left window corner, within the monitor area `(x, y, width, height)`
+    /// except for pixel coordinates, which are only offset by the monitor position.
+    /// `Last` without remembered position falls back to the center of the monitor.
+    pub fn calculate(
+        &self,
+        monitor: (i32, i32, i32, i32),
+        window_width: i32,
+        window_height: i32,
+        last_position: Option<(i32, i32)>,
+    ) -> (i32, i32) {
+        let (monitor_x, monitor_y, monitor_width, monitor_height) = monitor;
+        let (x, y) = match self {
+            WindowPlacement::Grid(horizontal_section, vertical_section) => {
+                let section_width = monitor_width / GRID_SECTIONS;
+                let section_height = monitor_height / GRID_SECTIONS;
+                (
+                    (horizontal_section - 1) * section_width + (section_width - window_width) / 2,
+                    (vertical_section - 1) * section_height + (section_height - window_height) / 2,
+                )
+            }
+            WindowPlacement::Absolute(x, y) => return (monitor_x + x, monitor_y + y),
+            WindowPlacement::Anchor(anchor) => {
+                let (horizontal, vertical) = anchor.alignment();
+                (
+                    (monitor_width - window_width) * horizontal / 2,
+                    (monitor_height - window_height) * vertical / 2,
+                )
+            }
+            WindowPlacement::Last => match last_position {
+                // Remembered position is already absolute (not relative to the monitor)
+                Some(position) => return position,
+                None => {
+                    return WindowPlacement::Anchor(Anchor::Center).calculate(
+                        monitor,
+                        window_width,
+                        window_height,
+                        None,
+                    )
+                }
+            },
+        };
+        (monitor_x + x.max(0), monitor_y + y.max(0))
+    }
+}
+
+fn positions_file() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(POSITIONS_DIR).join(POSITIONS_FILE))
+}
+
+fn read_positions() -> Vec<(u32, i32, i32)> {
+    let content = positions_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(|field| field.trim());
+            Some((
+                fields.next()?.parse().ok()?,
+                fields.next()?.parse().ok()?,
+                fields.next()?.parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Window position remembered from the last run of the channel.
+pub fn load_last_position(channel_no: u32) -> Option<(i32, i32)> {
+    read_positions()
+        .into_iter()
+        .find(|(channel, _, _)| *channel == channel_no)
+        .map(|(_, x, y)| (x, y))
+}
+
+/// Remembers window position of the channel for the next run (`--window_position last`).
+pub fn save_last_position(channel_no: u32, position: (i32, i32)) -> Result<(), String> {
+    let path = positions_file().ok_or("Unknown home directory")?;
+    let mut positions = read_positions();
+    positions.retain(|(channel, _, _)| *channel != channel_no);
+    positions.push((channel_no, position.0, position.1));
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let content: String = positions
+        .iter()
+        .map(|(channel, x, y)| format!("{},{},{}\n", channel, x, y))
+        .collect();
+    fs::write(&path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_placements() {
+        assert_eq!(
+            WindowPlacement::parse("2_3"),
+            Ok(WindowPlacement::Grid(2, 3))
+        );
+        assert_eq!(
+            WindowPlacement::parse("100, -20"),
+            Ok(WindowPlacement::Absolute(100, -20))
+        );
+        assert_eq!(
+            WindowPlacement::parse("top-right"),
+            Ok(WindowPlacement::Anchor(Anchor::TopRight))
+        );
+        assert_eq!(WindowPlacement::parse(" last "), Ok(WindowPlacement::Last));
+    }
+
+    #[test]
+    fn rejects_invalid_placements() {
+        assert!(WindowPlacement::parse("0_1").is_err());
+        assert!(WindowPlacement::parse("5_1").is_err());
+        assert!(WindowPlacement::parse("1,x").is_err());
+        assert!(WindowPlacement::parse("middle").is_err());
+    }
+
+    #[test]
+    fn calculates_position_on_monitor() {
+        let monitor = (1920, 0, 1920, 1080);
+        assert_eq!(
+            WindowPlacement::Anchor(Anchor::BottomRight).calculate(monitor, 400, 200, None),
+            (1920 + 1520, 880)
+        );
+        assert_eq!(
+            WindowPlacement::Grid(1, 1).calculate(monitor, 400, 200, None),
+            (1920 + 40, 35)
+        );
+        assert_eq!(
+            WindowPlacement::Last.calculate(monitor, 400, 200, Some((10, 20))),
+            (10, 20)
+        );
+        assert_eq!(
+            WindowPlacement::Last.calculate(monitor, 400, 200, None),
+            (1920 + 760, 440)
+        );
+    }
+
+    #[test]
+    fn keeps_negative_pixel_coordinates() {
+        let monitor = (1920, 0, 1920, 1080);
+        assert_eq!(
+            WindowPlacement::Absolute(-100, 40).calculate(monitor, 400, 200, None),
+            (1820, 40)
+        );
+        assert_eq!(
+            WindowPlacement::Absolute(-100, -40).calculate((0, 0, 1920, 1080), 400, 200, None),
+            (-100, -40)
+        );
+        // Window larger than the monitor stays at its edge
+        assert_eq!(
+            WindowPlacement::Anchor(Anchor::Center).calculate(monitor, 2000, 200, None),
+            (1920, 440)
+        );
+    }
+}
@@ -0,0 +1,314 @@
+use crate::measurement::{Coupling, MeasurementFlags, SiPrefix, Unit};
+use raylib::prelude::*;
+
+const GHOST_ALPHA: f32 = 0.08;
+const DIGIT_WIDTH_RATIO: f32 = 0.45;
+const DIGIT_SPACING_RATIO: f32 = 0.15;
+const SEGMENT_THICKNESS_RATIO: f32 = 0.11;
+const SEGMENT_GAP_RATIO: f32 = 0.015;
+
+// Segment bits, 7-segment display uses A..F and G1 | G2 as a single middle segment
+const A: u16 = 1 << 0; // top
+const B: u16 = 1 << 1; // top right
+const C: u16 = 1 << 2; // bottom right
+const D: u16 = 1 << 3; // bottom
+const E: u16 = 1 << 4; // bottom left
+const F: u16 = 1 << 5; // top left
+const G1: u16 = 1 << 6; // middle left
+const G2: u16 = 1 << 7; // middle right
+const H: u16 = 1 << 8; // diagonal top left
+const I: u16 = 1 << 9; // vertical top center
+const J: u16 = 1 << 10; // diagonal top right
+const K: u16 = 1 << 11; // diagonal bottom left
+const L: u16 = 1 << 12; // vertical bottom center
+const M: u16 = 1 << 13; // diagonal bottom right
+const G: u16 = G1 | G2;
+const SEVEN_SEGMENTS: u16 = A | B | C | D | E | F | G;
+const FOURTEEN_SEGMENTS: u16 = SEVEN_SEGMENTS | H | I | J | K | L | M;
+
+/// Kind of segmented digits drawn by `SegmentDisplay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentStyle {
+    Seven,
+    Fourteen,
+}
+
+/// Vector renderer of LCD-like segmented digits, with unlit ("ghost") segments visible.
+pub struct SegmentDisplay {
+    style: SegmentStyle,
+    digit_width: f32,
+    digit_height: f32,
+    spacing: f32,
+    thickness: f32,
+    gap: f32,
+}
+
+impl SegmentDisplay {
+    pub fn new(style: SegmentStyle, digit_height: f32) -> Self {
+        Self {
+            style,
+            digit_width: digit_height * DIGIT_WIDTH_RATIO,
+            digit_height,
+            spacing: digit_height * DIGIT_SPACING_RATIO,
+            thickness: digit_height * SEGMENT_THICKNESS_RATIO,
+            gap: digit_height * SEGMENT_GAP_RATIO,
+        }
+    }
+
+    /// Draws the text from `pos` (top left corner); a decimal point is drawn in the preceding digit.
+    pub fn draw_text(&self, d: &mut RaylibDrawHandle<'_>, text: &str, pos: Vector2, color: Color) {
+        let mut cells: Vec<(u16, bool)> = vec![];
+        for c in text.chars() {
+            match (c, cells.last_mut()) {
+                ('.' | ',', Some((_, dot))) if !*dot => *dot = true,
+                ('.' | ',', _) => cells.push((0, true)),
+                _ => cells.push((self.glyph(c), false)),
+            }
+        }
+        let all_segments = match self.style {
+            SegmentStyle::Seven => SEVEN_SEGMENTS,
+            SegmentStyle::Fourteen => FOURTEEN_SEGMENTS,
+        };
+        let ghost_color = color.fade(GHOST_ALPHA);
+        for (i, (segments, dot)) in cells.iter().enumerate() {
+            let x = pos.x + i as f32 * (self.digit_width + self.spacing);
+            self.draw_cell(d, x, pos.y, all_segments & !segments, false, ghost_color);
+            self.draw_cell(d, x, pos.y, *segments, *dot, color);
+        }
+    }
+
+    fn glyph(&self, c: char) -> u16 {
+        match self.style {
+            SegmentStyle::Seven => seven_segment_glyph(c),
+            SegmentStyle::Fourteen => fourteen_segment_glyph(c),
+        }
+    }
+
+    fn draw_cell(
+        &self,
+        d: &mut RaylibDrawHandle<'_>,
+        x: f32,
+        y: f32,
+        segments: u16,
+        dot: bool,
+        color: Color,
+    ) {
+        let (w, h, t) = (self.digit_width, self.digit_height, self.thickness);
+        let (left, right, top, bottom) = (x, x + w, y, y + h);
+        let (center_x, center_y) = (x + w / 2.0, y + h / 2.0);
+        let v = Vector2::new;
+
+        let mut straight = vec![
+            (A, v(left, top), v(right, top)),
+            (B, v(right, top), v(right, center_y)),
+            (C, v(right, center_y), v(right, bottom)),
+            (D, v(left, bottom), v(right, bottom)),
+            (E, v(left, center_y), v(left, bottom)),
+            (F, v(left, top), v(left, center_y)),
+        ];
+        match self.style {
+            SegmentStyle::Seven => straight.push((G, v(left, center_y), v(right, center_y))),
+            SegmentStyle::Fourteen => straight.extend([
+                (G1, v(left, center_y), v(center_x, center_y)),
+                (G2, v(center_x, center_y), v(right, center_y)),
+                (I, v(center_x, top), v(center_x, center_y)),
+                (L, v(center_x, center_y), v(center_x, bottom)),
+            ]),
+        }
+        for (segment, start, end) in straight {
+            if segments & segment == segment {
+                self.draw_segment(d, start, end, color);
+            }
+        }
+
+        if self.style == SegmentStyle::Fourteen {
+            let diagonals = [
+                (
+                    H,
+                    v(left + t, top + t),
+                    v(center_x - t / 2.0, center_y - t / 2.0),
+                ),
+                (
+                    J,
+                    v(right - t, top + t),
+                    v(center_x + t / 2.0, center_y - t / 2.0),
+                ),
+                (
+                    K,
+                    v(left + t, bottom - t),
+                    v(center_x - t / 2.0, center_y + t / 2.0),
+                ),
+                (
+                    M,
+                    v(right - t, bottom - t),
+                    v(center_x + t / 2.0, center_y + t / 2.0),
+                ),
+            ];
+            for (segment, start, end) in diagonals {
+                if segments & segment == segment {
+                    d.draw_line_ex(start, end, t * 0.7, color);
+                }
+            }
+        }
+
+        if dot {
+            d.draw_rectangle_v(
+                v(right + (self.spacing - t) / 2.0, bottom - t / 2.0),
+                v(t, t),
+                color,
+            );
+        }
+    }
+
+    /// Draws one segment as a hexagon with pointed ends.
+    fn draw_segment(
+        &self,
+        d: &mut RaylibDrawHandle<'_>,
+        start: Vector2,
+        end: Vector2,
+        color: Color,
+    ) {
+        let length = (end - start).length();
+        let along = (end - start) / length;
+        let across = Vector2::new(-along.y, along.x);
+        let half = self.thickness / 2.0;
+        let start = start + along * self.gap;
+        let end = end - along * self.gap;
+        let points = [
+            start,
+            start + along * half + across * half,
+            end - along * half + across * half,
+            end,
+            end - along * half - across * half,
+            start + along * half - across * half,
+        ];
+        fill_convex(d, &points, color);
+    }
+}
+
+/// Fills a convex polygon as a fan of triangles, in the vertex order required by raylib.
+fn fill_convex(d: &mut RaylibDrawHandle<'_>, points: &[Vector2], color: Color) {
+    for i in 1..points.len() - 1 {
+        let (a, b, c) = (points[0], points[i], points[i + 1]);
+        let cross = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+        if cross < 0.0 {
+            d.draw_triangle(a, b, c, color);
+        } else {
+            d.draw_triangle(a, c, b, color);
+        }
+    }
+}
+
+fn seven_segment_glyph(c: char) -> u16 {
+    match c {
+        '0' | 'O' | 'D' => A | B | C | D | E | F,
+        '1' | 'I' => B | C,
+        '2' | 'Z' => A | B | G | D | E,
+        '3' => A | B | G | C | D,
+        '4' => F | G | B | C,
+        '5' | 'S' | 's' => A | F | G | C | D,
+        '6' => A | F | G | E | D | C,
+        '7' => A | B | C,
+        '8' | 'B' => SEVEN_SEGMENTS,
+        '9' | 'g' => A | B | C | D | F | G,
+        '-' => G,
+        '_' => D,
+        'A' | 'a' => A | B | C | E | F | G,
+        'b' => C | D | E | F | G,
+        'C' => A | D | E | F,
+        'c' => D | E | G,
+        'd' => B | C | D | E | G,
+        'E' | 'e' => A | D | E | F | G,
+        'F' | 'f' => A | E | F | G,
+        'H' => B | C | E | F | G,
+        'h' => C | E | F | G,
+        'J' | 'j' => B | C | D | E,
+        'L' | 'l' => D | E | F,
+        'N' | 'n' | 'M' | 'm' => C | E | G,
+        'o' => C | D | E | G,
+        'P' | 'p' => A | B | E | F | G,
+        'R' | 'r' => E | G,
+        'T' | 't' => D | E | F | G,
+        'U' | 'V' | 'W' => B | C | D | E | F,
+        'u' | 'v' | 'w' => C | D | E,
+        'Y' | 'y' => B | C | D | F | G,
+        _ => 0,
+    }
+}
+
+fn fourteen_segment_glyph(c: char) -> u16 {
+    match c.to_ascii_uppercase() {
+        '0' => A | B | C | D | E | F | J | K,
+        '1' => B | C | J,
+        '2' => A | B | G | D | E,
+        '3' => A | B | G2 | C | D,
+        '4' => F | G | B | C,
+        '5' => A | F | G | C | D,
+        '6' => A | F | G | E | D | C,
+        '7' => A | B | C,
+        '8' => SEVEN_SEGMENTS,
+        '9' => A | B | C | D | F | G,
+        '-' => G,
+        '_' => D,
+        '+' => G | I | L,
+        '/' => J | K,
+        'A' => A | B | C | E | F | G,
+        'B' => A | B | C | D | I | L | G2,
+        'C' => A | D | E | F,
+        'D' => A | B | C | D | I | L,
+        'E' => A | D | E | F | G1,
+        'F' => A | E | F | G1,
+        'G' => A | C | D | E | F | G2,
+        'H' => B | C | E | F | G,
+        'I' => A | D | I | L,
+        'J' => B | C | D | E,
+        'K' => E | F | G1 | J | M,
+        'L' => D | E | F,
+        'M' => B | C | E | F | H | J,
+        'N' => B | C | E | F | H | M,
+        'O' => A | B | C | D | E | F,
+        'P' => A | B | E | F | G,
+        'Q' => A | B | C | D | E | F | M,
+        'R' => A | B | E | F | G | M,
+        'S' => A | C | D | F | G,
+        'T' => A | I | L,
+        'U' => B | C | D | E | F,
+        'V' => E | F | K | J,
+        'W' => B | C | E | F | K | M,
+        'X' => H | J | K | M,
+        'Y' => H | J | L,
+        'Z' => A | D | J | K,
+        _ => 0,
+    }
+}
+
+/// Draws LCD annunciators in a row from `pos`; active ones are lit, others are ghosted.
+pub fn draw_annunciators(
+    d: &mut RaylibDrawHandle<'_>,
+    pos: Vector2,
+    font_size: i32,
+    unit: &Unit,
+    flags: &MeasurementFlags,
+    color: Color,
+) {
+    let labels = [
+        ("AC", unit.coupling == Coupling::Ac),
+        ("DC", unit.coupling == Coupling::Dc),
+        ("HOLD", flags.hold),
+        ("REL", flags.rel),
+        ("OL", flags.overload),
+        ("n", unit.prefix == SiPrefix::Nano),
+        ("µ", unit.prefix == SiPrefix::Micro),
+        ("m", unit.prefix == SiPrefix::Milli),
+        ("k", unit.prefix == SiPrefix::Kilo),
+        ("M", unit.prefix == SiPrefix::Mega),
+    ];
+    let ghost_color = color.fade(GHOST_ALPHA * 2.0);
+    let mut x = pos.x as i32;
+    let y = pos.y as i32;
+    for (label, lit) in labels {
+        let label_color = if lit { color } else { ghost_color };
+        d.draw_text(label, x, y, font_size, label_color);
+        x += measure_text(label, font_size) + font_size;
+    }
+}
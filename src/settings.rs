@@ -0,0 +1,622 @@
+use clap::{Arg, ArgMatches, Command};
+use raylib::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+const PROFILES_DIR: &str = ".open-modern-multimeter/profiles";
+const PROFILE_EXTENSION: &str = "conf";
+const PROFILE_OPTION: &str = "profile";
+// Options naming where other options are read from, not settings themselves
+const SOURCE_OPTIONS: [&str; 2] = [PROFILE_OPTION, "setup"];
+const MARGIN: f32 = 10.0;
+const ROW_HEIGHT: f32 = 22.0;
+const FONT_SIZE: i32 = 16;
+const NAME_WIDTH: f32 = 200.0;
+const BUTTON_WIDTH: f32 = 90.0;
+const PANEL_COLOR: Color = Color::new(16, 16, 16, 240);
+const SELECTED_COLOR: Color = Color::DARKGRAY;
+const BUTTON_COLOR: Color = Color::GRAY;
+const NAME_COLOR: Color = Color::LIGHTGRAY;
+const VALUE_COLOR: Color = Color::WHITE;
+const MESSAGE_COLOR: Color = Color::GREEN;
+const ERROR_COLOR: Color = Color::RED;
+const BUTTONS: [&str; 3] = ["APPLY", "SAVE", "CLOSE"];
+
+/// Option of the command line edited in the settings panel.
+struct Setting {
+    name: String,
+    values: Vec<String>, // One for each occurrence of a repeated option (i.e. for each port)
+    repeated: bool,
+    choices: Vec<String>,  // Possible values, empty for a free text
+    defaults: Vec<String>, // Values without the option
+}
+
+/// What the user asked for in the settings panel.
+pub enum SettingsEvent {
+    Apply(Vec<String>), // Command line with the edited settings
+    Close,
+}
+
+/// Options which are settings: all options with a value, except the profile and the setup.
+fn settings_args<'a, 'help>(command: &'a Command<'help>) -> impl Iterator<Item = &'a Arg<'help>> {
+    command
+        .get_arguments()
+        .filter(|arg| arg.is_takes_value_set() && !SOURCE_OPTIONS.contains(&arg.get_id()))
+}
+
+fn long_name<'help>(arg: &Arg<'help>) -> &'help str {
+    arg.get_long().unwrap_or_else(|| arg.get_id())
+}
+
+/// Overlay in the window for changing all command line options while running. Edited options
+/// are applied as a new command line, so they are validated the same way as on start.
+pub struct SettingsPanel {
+    program: String,
+    profile: String,
+    settings: Vec<Setting>,
+    selected: usize, // Row 0 is the profile name, settings follow
+    scroll: usize,
+    message: Option<(String, bool)>, // Text and whether it is an error
+}
+
+impl SettingsPanel {
+    pub fn new(command: &Command<'_>, matches: &ArgMatches, profile: &str) -> Self {
+        let settings = settings_args(command)
+            .map(|arg| {
+                let mut choices: Vec<String> = arg
+                    .get_possible_values()
+                    .map(|values| values.iter().map(|v| v.get_name().to_string()).collect())
+                    .unwrap_or_default();
+                // Switches given as 1 or 0
+                if choices.is_empty()
+                    && matches!(arg.get_default_values(), [v] if *v == "0" || *v == "1")
+                {
+                    choices = vec!["0".to_string(), "1".to_string()];
+                }
+                let mut values: Vec<String> = matches
+                    .values_of(arg.get_id())
+                    .map(|values| values.map(String::from).collect())
+                    .unwrap_or_default();
+                if values.is_empty() {
+                    values.push(String::new());
+                }
+                let mut defaults: Vec<String> = arg
+                    .get_default_values()
+                    .iter()
+                    .map(|value| value.to_string_lossy().into_owned())
+                    .collect();
+                if defaults.is_empty() {
+                    defaults.push(String::new());
+                }
+                Setting {
+                    name: long_name(arg).to_string(),
+                    values,
+                    repeated: arg.is_multiple_occurrences_set(),
+                    choices,
+                    defaults,
+                }
+            })
+            .collect();
+        Self {
+            program: command.get_name().to_string(),
+            profile: profile.to_string(),
+            settings,
+            selected: 0,
+            scroll: 0,
+            message: None,
+        }
+    }
+
+    pub fn show_message(&mut self, text: &str, error: bool) {
+        self.message = Some((text.to_string(), error));
+    }
+
+    /// Command line with the settings, to be parsed like the one given to the program.
+    fn args(&self) -> Vec<String> {
+        let mut args = vec![self.program.clone()];
+        for setting in &self.settings {
+            // `--name=value`, so negative numbers (i.e. -5,5) are not taken for options
+            args.extend(
+                setting
+                    .values
+                    .iter()
+                    .map(|value| format!("--{}={}", setting.name, value)),
+            );
+        }
+        args
+    }
+
+    /// Options with values other than defaults, `(option, value)` for each value.
+    fn changed_options(&self) -> Vec<(String, String)> {
+        // Only changed options, so a profile does not override a setup with defaults
+        self.settings
+            .iter()
+            .filter(|setting| setting.values != setting.defaults)
+            .flat_map(|setting| {
+                setting
+                    .values
+                    .iter()
+                    .map(|value| (setting.name.clone(), value.clone()))
+            })
+            .collect()
+    }
+
+    fn save(&mut self) {
+        if self.profile.trim().is_empty() {
+            self.show_message("Enter a profile name to save settings", true);
+            return;
+        }
+        let settings = self.changed_options();
+        match save_profile(self.profile.trim(), &settings) {
+            Ok(path) => self.show_message(&format!("Saved to {}", path.display()), false),
+            Err(e) => self.show_message(&e, true),
+        }
+    }
+
+    /// Setting and index of its value in rows after the profile name; each value of a
+    /// repeated option has its own row.
+    fn setting_rows(&self) -> Vec<(usize, usize)> {
+        self.settings
+            .iter()
+            .enumerate()
+            .flat_map(|(i, setting)| (0..setting.values.len()).map(move |j| (i, j)))
+            .collect()
+    }
+
+    fn row_count(&self) -> usize {
+        self.setting_rows().len() + 1
+    }
+
+    fn row(&self, row: usize) -> (String, &str, &[String]) {
+        match row {
+            0 => (PROFILE_OPTION.to_string(), &self.profile, &[]),
+            _ => {
+                let (i, j) = self.setting_rows()[row - 1];
+                let setting = &self.settings[i];
+                let name = if setting.repeated {
+                    format!("{} #{}", setting.name, j + 1)
+                } else {
+                    setting.name.clone()
+                };
+                (name, &setting.values[j], &setting.choices)
+            }
+        }
+    }
+
+    fn value_mut(&mut self, row: usize) -> &mut String {
+        match row {
+            0 => &mut self.profile,
+            _ => {
+                let (i, j) = self.setting_rows()[row - 1];
+                &mut self.settings[i].values[j]
+            }
+        }
+    }
+
+    /// Adds a value of a repeated option after the one in the row (a copy of it), or removes
+    /// the value in the row (the last one stays).
+    fn add_or_remove_value(&mut self, row: usize, add: bool) {
+        let (i, j) = match row.checked_sub(1).map(|row| self.setting_rows()[row]) {
+            Some((i, j)) if self.settings[i].repeated => (i, j),
+            _ => return,
+        };
+        let values = &mut self.settings[i].values;
+        if add {
+            values.insert(j + 1, values[j].clone());
+            self.selected = row + 1;
+        } else if values.len() > 1 {
+            values.remove(j);
+            self.selected = self.selected.min(self.row_count() - 1);
+        }
+    }
+
+    /// Selects the next (or previous) possible value of the row.
+    fn cycle(&mut self, row: usize, step: isize) {
+        let (_, value, choices) = self.row(row);
+        if choices.is_empty() {
+            return;
+        }
+        let current = choices
+            .iter()
+            .position(|choice| choice == value)
+            .unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(choices.len() as isize) as usize;
+        let next = choices[next].clone();
+        *self.value_mut(row) = next;
+    }
+
+    fn visible_rows(screen_height: f32) -> usize {
+        (((screen_height - 2.0 * MARGIN) / ROW_HEIGHT) as usize)
+            .saturating_sub(2)
+            .max(1)
+    }
+
+    fn row_area(&self, row: usize, screen_width: f32) -> Rectangle {
+        Rectangle::new(
+            MARGIN,
+            MARGIN + (1 + row - self.scroll) as f32 * ROW_HEIGHT,
+            screen_width - 2.0 * MARGIN,
+            ROW_HEIGHT,
+        )
+    }
+
+    fn button_area(index: usize, screen_height: f32) -> Rectangle {
+        Rectangle::new(
+            MARGIN + index as f32 * (BUTTON_WIDTH + MARGIN),
+            screen_height - MARGIN - ROW_HEIGHT,
+            BUTTON_WIDTH,
+            ROW_HEIGHT,
+        )
+    }
+
+    /// Handles keyboard and mouse: Up/Down select an option, Left/Right (or a click) change
+    /// a value with choices, typed text edits the other ones.
+    pub fn update(&mut self, rl: &mut RaylibHandle) -> Option<SettingsEvent> {
+        let (screen_width, screen_height) =
+            (rl.get_screen_width() as f32, rl.get_screen_height() as f32);
+        if rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return Some(SettingsEvent::Close);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return Some(SettingsEvent::Apply(self.args()));
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.selected = (self.selected + 1).min(self.row_count() - 1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_RIGHT) {
+            self.cycle(self.selected, 1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_LEFT) {
+            self.cycle(self.selected, -1);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_INSERT) {
+            self.add_or_remove_value(self.selected, true);
+        }
+        if rl.is_key_pressed(KeyboardKey::KEY_DELETE) {
+            self.add_or_remove_value(self.selected, false);
+        }
+        if self.row(self.selected).2.is_empty() {
+            let value = self.value_mut(self.selected);
+            loop {
+                let key = unsafe { raylib::ffi::GetCharPressed() };
+                if key <= 0 {
+                    break;
+                }
+                if let Some(c) = char::from_u32(key as u32) {
+                    value.push(c);
+                }
+            }
+            if rl.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+                value.pop();
+            }
+        }
+
+        let visible_rows = SettingsPanel::visible_rows(screen_height);
+        let max_scroll = self.row_count().saturating_sub(visible_rows);
+        let wheel = rl.get_mouse_wheel_move();
+        if wheel > 0.0 {
+            self.scroll = self.scroll.saturating_sub(1);
+        } else if wheel < 0.0 {
+            self.scroll = (self.scroll + 1).min(max_scroll);
+        } else if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + visible_rows {
+            self.scroll = self.selected + 1 - visible_rows;
+        }
+
+        if rl.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) {
+            let mouse = rl.get_mouse_position();
+            match (0..BUTTONS.len()).find(|&i| {
+                SettingsPanel::button_area(i, screen_height).check_collision_point_rec(mouse)
+            }) {
+                Some(0) => return Some(SettingsEvent::Apply(self.args())),
+                Some(1) => self.save(),
+                Some(_) => return Some(SettingsEvent::Close),
+                None => {
+                    let rows = self.scroll..(self.scroll + visible_rows).min(self.row_count());
+                    if let Some(row) = rows.into_iter().find(|&row| {
+                        self.row_area(row, screen_width)
+                            .check_collision_point_rec(mouse)
+                    }) {
+                        if row == self.selected {
+                            self.cycle(row, 1);
+                        }
+                        self.selected = row;
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, d: &mut RaylibDrawHandle<'_>) {
+        let (screen_width, screen_height) =
+            (d.get_screen_width() as f32, d.get_screen_height() as f32);
+        d.draw_rectangle(0, 0, screen_width as i32, screen_height as i32, PANEL_COLOR);
+        d.draw_text(
+            "SETTINGS  (Up/Down: option, Left/Right: value, Ins/Del: add/remove port value, Enter: apply, Esc: close)",
+            MARGIN as i32,
+            MARGIN as i32,
+            FONT_SIZE,
+            NAME_COLOR,
+        );
+
+        let visible_rows = SettingsPanel::visible_rows(screen_height);
+        for row in self.scroll..(self.scroll + visible_rows).min(self.row_count()) {
+            let area = self.row_area(row, screen_width);
+            let (name, value, choices) = self.row(row);
+            let selected = row == self.selected;
+            if selected {
+                d.draw_rectangle_rec(area, SELECTED_COLOR);
+            }
+            let text_y = (area.y + (ROW_HEIGHT - FONT_SIZE as f32) / 2.0) as i32;
+            d.draw_text(
+                &name,
+                (area.x + MARGIN) as i32,
+                text_y,
+                FONT_SIZE,
+                NAME_COLOR,
+            );
+            let value = if !choices.is_empty() {
+                format!("< {} >", value)
+            } else if selected {
+                format!("{}_", value)
+            } else {
+                value.to_string()
+            };
+            d.draw_text(
+                &value,
+                (area.x + NAME_WIDTH) as i32,
+                text_y,
+                FONT_SIZE,
+                VALUE_COLOR,
+            );
+        }
+
+        for (i, label) in BUTTONS.iter().enumerate() {
+            let button = SettingsPanel::button_area(i, screen_height);
+            d.draw_rectangle_rec(button, BUTTON_COLOR);
+            d.draw_text(
+                label,
+                (button.x + (button.width - measure_text(label, FONT_SIZE) as f32) / 2.0) as i32,
+                (button.y + (button.height - FONT_SIZE as f32) / 2.0) as i32,
+                FONT_SIZE,
+                VALUE_COLOR,
+            );
+        }
+        if let Some((text, error)) = &self.message {
+            let button = SettingsPanel::button_area(BUTTONS.len(), screen_height);
+            d.draw_text(
+                text,
+                button.x as i32,
+                (button.y + (button.height - FONT_SIZE as f32) / 2.0) as i32,
+                FONT_SIZE,
+                if *error { ERROR_COLOR } else { MESSAGE_COLOR },
+            );
+        }
+    }
+}
+
+/// File name of the profile; the extension is appended, so a name may contain dots,
+/// but not path separators.
+fn profile_file_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+        return Err(format!("Invalid profile name '{}'", name));
+    }
+    Ok(format!("{}.{}", name, PROFILE_EXTENSION))
+}
+
+fn profile_file(name: &str) -> Result<PathBuf, String> {
+    let file_name = profile_file_name(name)?;
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .ok_or("Unknown home directory")?;
+    Ok(PathBuf::from(home).join(PROFILES_DIR).join(file_name))
+}
+
+/// Reads settings saved under the profile name, one `option = value` per line
+/// (a repeated option is on more lines).
+pub fn load_profile(name: &str) -> Result<Vec<(String, String)>, String> {
+    let path = profile_file(name)?;
+    let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (option, value) = line.split_once('=').ok_or_else(|| {
+                format!(
+                    "Invalid line '{}' in {}, expected 'option = value'",
+                    line,
+                    path.display()
+                )
+            })?;
+            Ok((option.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn save_profile(name: &str, settings: &[(String, String)]) -> Result<PathBuf, String> {
+    let path = profile_file(name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let content: String = settings
+        .iter()
+        .map(|(option, value)| format!("{} = {}\n", option, value))
+        .collect();
+    fs::write(&path, content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Command line with options from other sources (i.e. the profile and the setup), except
+/// the ones given on the command line, which override them. Each option is taken from the first
+/// source which has it, so the order of sources is their precedence (the profile before the
+/// setup); sources are `(description, options)`.
+pub fn merge_options(
+    command: &Command<'_>,
+    matches: &ArgMatches,
+    sources: &[(String, Vec<(String, String)>)],
+) -> Result<Vec<String>, String> {
+    for (source, options) in sources {
+        for (i, (option, _)) in options.iter().enumerate() {
+            let arg = settings_args(command)
+                .find(|arg| long_name(arg) == option)
+                .ok_or_else(|| format!("Unknown option '{}' in {}", option, source))?;
+            if !arg.is_multiple_occurrences_set()
+                && options[..i].iter().any(|(previous, _)| previous == option)
+            {
+                return Err(format!(
+                    "Option '{}' is given more than once in {}, but it is not set per channel",
+                    option, source
+                ));
+            }
+        }
+    }
+    let mut args = vec![command.get_name().to_string()];
+    for arg in settings_args(command) {
+        let name = long_name(arg);
+        let values: Vec<String> = match matches.values_of(arg.get_id()) {
+            Some(values) if matches.occurrences_of(arg.get_id()) > 0 => {
+                values.map(String::from).collect()
+            }
+            _ => sources
+                .iter()
+                .map(|(_, options)| {
+                    options
+                        .iter()
+                        .filter(|(option, _)| option == name)
+                        .map(|(_, value)| value.clone())
+                        .collect::<Vec<String>>()
+                })
+                .find(|values| !values.is_empty())
+                .unwrap_or_default(),
+        };
+        args.extend(values.iter().map(|value| format!("--{}={}", name, value)));
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Command<'static> {
+        Command::new("omm")
+            .arg(
+                Arg::new("port")
+                    .long("port")
+                    .takes_value(true)
+                    .multiple_occurrences(true),
+            )
+            .arg(
+                Arg::new("limits")
+                    .long("limits")
+                    .multiple_occurrences(true)
+                    .default_value(""),
+            )
+            .arg(Arg::new("baud").long("baud").default_value("9600"))
+    }
+
+    #[test]
+    fn keeps_empty_values_of_repeated_options() {
+        let matches = command().get_matches_from([
+            "omm",
+            "--port=a",
+            "--port=b",
+            "--limits=",
+            "--limits=4.75,5.25",
+        ]);
+        let panel = SettingsPanel::new(&command(), &matches, "");
+        assert_eq!(
+            panel.args(),
+            [
+                "omm",
+                "--port=a",
+                "--port=b",
+                "--limits=",
+                "--limits=4.75,5.25",
+                "--baud=9600"
+            ]
+        );
+    }
+
+    #[test]
+    fn adds_and_removes_values_of_repeated_options() {
+        let matches = command().get_matches_from(["omm", "--port=a"]);
+        let mut panel = SettingsPanel::new(&command(), &matches, "");
+        assert_eq!(panel.row(1).0, "port #1");
+        panel.add_or_remove_value(1, true);
+        *panel.value_mut(2) = "b c".to_string();
+        assert_eq!(panel.row(2), ("port #2".to_string(), "b c", &[][..]));
+        panel.add_or_remove_value(1, false);
+        panel.add_or_remove_value(1, false);
+        assert_eq!(
+            panel.args()[1..],
+            ["--port=b c", "--limits=", "--baud=9600"]
+        );
+        // Options which are not repeated have a single value
+        panel.add_or_remove_value(3, true);
+        assert_eq!(panel.row_count(), 4);
+    }
+
+    #[test]
+    fn saves_only_changed_options() {
+        let matches = command().get_matches_from(["omm", "--port=a", "--baud=9600"]);
+        let panel = SettingsPanel::new(&command(), &matches, "");
+        assert_eq!(
+            panel.changed_options(),
+            [("port".to_string(), "a".to_string())]
+        );
+    }
+
+    #[test]
+    fn merges_command_line_profile_and_setup_in_order() {
+        let matches = command().get_matches_from(["omm", "--baud=115200"]);
+        let option = |name: &str, value: &str| (name.to_string(), value.to_string());
+        let sources = [
+            (
+                "profile".to_string(),
+                vec![option("baud", "19200"), option("limits", "1,2")],
+            ),
+            (
+                "setup".to_string(),
+                vec![
+                    option("port", "a"),
+                    option("port", "b"),
+                    option("limits", "3,4"),
+                ],
+            ),
+        ];
+        assert_eq!(
+            merge_options(&command(), &matches, &sources),
+            Ok(vec![
+                "omm".to_string(),
+                "--port=a".to_string(),
+                "--port=b".to_string(),
+                "--limits=1,2".to_string(),
+                "--baud=115200".to_string(),
+            ])
+        );
+        let unknown = [("setup".to_string(), vec![option("speed", "1")])];
+        assert!(merge_options(&command(), &matches, &unknown).is_err());
+        let repeated = [(
+            "setup 'bench'".to_string(),
+            vec![option("baud", "9600"), option("baud", "19200")],
+        )];
+        let error = merge_options(&command(), &matches, &repeated).unwrap_err();
+        assert!(error.contains("'baud'") && error.contains("setup 'bench'"));
+    }
+
+    #[test]
+    fn appends_extension_to_profile_name() {
+        assert_eq!(profile_file_name("bench").unwrap(), "bench.conf");
+        assert_eq!(profile_file_name("psu.5v").unwrap(), "psu.5v.conf");
+        for name in ["", ".", "..", "../bench", "a/b", "a\\b"] {
+            assert!(profile_file_name(name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::PathBuf;
+use toml::value::{Table, Value};
+
+const USER_CONFIG_FILE: &str = ".open-modern-multimeter/config.toml";
+const PROJECT_CONFIG_FILE: &str = "open-modern-multimeter.toml";
+const SETUPS_TABLE: &str = "setup";
+const CHANNELS_ARRAY: &str = "channel";
+
+/// Config files with setups, the project one (in the current directory) first, so its setups
+/// win over the ones of the user with the same name.
+fn config_files() -> Vec<PathBuf> {
+    let mut files = vec![PathBuf::from(PROJECT_CONFIG_FILE)];
+    if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        files.push(PathBuf::from(home).join(USER_CONFIG_FILE));
+    }
+    files
+}
+
+/// Reads the named setup from config files as command line options `(option, value)`.
+///
+/// Keys of a setup are names of command line options; they can be grouped in sub-tables
+/// (i.e. `[setup.bench-a.display]`) and options of each port are given in
+/// `[[setup.bench-a.channel]]` tables.
+pub fn load_setup(name: &str) -> Result<Vec<(String, String)>, String> {
+    let mut available = vec![];
+    for path in config_files().into_iter().filter(|path| path.exists()) {
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let config: Value = content
+            .parse()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let setups = match config.get(SETUPS_TABLE).and_then(Value::as_table) {
+            Some(setups) => setups,
+            None => continue,
+        };
+        if let Some(setup) = setups.get(name) {
+            let setup = setup
+                .as_table()
+                .ok_or_else(|| format!("{}: setup '{}' is not a table", path.display(), name))?;
+            let mut options = vec![];
+            collect_options(setup, &mut options)
+                .map_err(|e| format!("{}: setup '{}': {}", path.display(), name, e))?;
+            return Ok(options);
+        }
+        available.extend(setups.keys().cloned());
+    }
+    Err(format!(
+        "Setup '{}' not found in {} or ~/{}, available setups: {}",
+        name,
+        PROJECT_CONFIG_FILE,
+        USER_CONFIG_FILE,
+        available.join(", ")
+    ))
+}
+
+fn collect_options(table: &Table, options: &mut Vec<(String, String)>) -> Result<(), String> {
+    for (key, value) in table {
+        match value {
+            Value::Table(group) => collect_options(group, options)?,
+            Value::Array(channels) if key == CHANNELS_ARRAY => {
+                collect_channel_options(channels, options)?
+            }
+            Value::Array(values) => {
+                for value in values {
+                    options.push((key.clone(), option_value(key, value)?));
+                }
+            }
+            value => options.push((key.clone(), option_value(key, value)?)),
+        }
+    }
+    Ok(())
+}
+
+/// Options of ports become repeated options, one value for each channel in order. A channel
+/// without an option given for other ones gets the value of the previous channel, like on
+/// the command line, so the first channel has to give all of them.
+fn collect_channel_options(
+    channels: &[Value],
+    options: &mut Vec<(String, String)>,
+) -> Result<(), String> {
+    let channels = channels
+        .iter()
+        .map(|channel| {
+            channel
+                .as_table()
+                .ok_or_else(|| format!("'{}' has to be an array of tables", CHANNELS_ARRAY))
+        })
+        .collect::<Result<Vec<&Table>, String>>()?;
+    let mut keys: Vec<&String> = channels.iter().flat_map(|channel| channel.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    for key in keys {
+        if !channels[0].contains_key(key) {
+            return Err(format!(
+                "'{}' is given for a later channel, but not for the first one",
+                key
+            ));
+        }
+        let mut last = String::new();
+        for channel in &channels {
+            if let Some(value) = channel.get(key) {
+                last = option_value(key, value)?;
+            }
+            options.push((key.clone(), last.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Value as given on the command line; booleans are `1` or `0`.
+fn option_value(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Integer(number) => Ok(number.to_string()),
+        Value::Float(number) => Ok(number.to_string()),
+        Value::Boolean(enabled) => Ok(if *enabled { "1" } else { "0" }.to_string()),
+        value => Err(format!(
+            "Invalid value of '{}', expected a string, number or boolean, not {}",
+            key,
+            value.type_str()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_options(config: &str) -> Result<Vec<(String, String)>, String> {
+        let config: Value = config.parse().unwrap();
+        let setup = config[SETUPS_TABLE]["bench"].as_table().unwrap();
+        let mut options = vec![];
+        collect_options(setup, &mut options)?;
+        Ok(options)
+    }
+
+    fn option(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn collects_options_of_groups_and_channels() {
+        let options = setup_options(
+            r#"
+            [setup.bench]
+            baud = 115200
+            [setup.bench.display]
+            enable_chart = true
+            [[setup.bench.channel]]
+            port = "/dev/ttyUSB0"
+            unit = "VDC"
+            [[setup.bench.channel]]
+            port = "/dev/ttyUSB1"
+            [[setup.bench.channel]]
+            port = "/dev/ttyUSB2"
+            unit = "mADC"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            options,
+            [
+                option("baud", "115200"),
+                option("port", "/dev/ttyUSB0"),
+                option("port", "/dev/ttyUSB1"),
+                option("port", "/dev/ttyUSB2"),
+                option("unit", "VDC"),
+                option("unit", "VDC"),
+                option("unit", "mADC"),
+                option("enable_chart", "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_option_missing_in_first_channel() {
+        let error = setup_options(
+            r#"
+            [[setup.bench.channel]]
+            port = "/dev/ttyUSB0"
+            [[setup.bench.channel]]
+            port = "/dev/ttyUSB1"
+            unit = "mADC"
+            "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("'unit'"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        assert!(setup_options("[setup.bench]\nchannel = [1, 2]").is_err());
+        assert!(setup_options("[setup.bench]\nport = [[1]]").is_err());
+        assert!(setup_options("[setup.bench]\nsince = 1979-05-27").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+/// Running statistics of valid readings since start or the last reset, updated incrementally
+/// (Welford's algorithm), so no readings need to be kept.
+pub struct Statistics {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    mean: f64,
+    m2: f64, // Sum of squared differences from the mean
+    started: Instant,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            min: f64::NAN,
+            max: f64::NAN,
+            mean: 0.0,
+            m2: 0.0,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn add_value(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Statistics::new();
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.mean)
+        } else {
+            None
+        }
+    }
+
+    /// Sample standard deviation.
+    pub fn std_dev(&self) -> Option<f64> {
+        if self.count > 1 {
+            Some((self.m2 / (self.count - 1) as f64).sqrt())
+        } else {
+            None
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics(values: &[f64]) -> Statistics {
+        let mut statistics = Statistics::new();
+        for value in values {
+            statistics.add_value(*value);
+        }
+        statistics
+    }
+
+    #[test]
+    fn calculates_mean_and_sample_deviation() {
+        let statistics = statistics(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert_eq!(statistics.count, 8);
+        assert_eq!((statistics.min, statistics.max), (2.0, 9.0));
+        assert_eq!(statistics.average(), Some(5.0));
+        assert!((statistics.std_dev().unwrap() - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn keeps_precision_with_large_offset() {
+        let statistics = statistics(&[1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0]);
+        assert_eq!(statistics.average(), Some(1e9 + 10.0));
+        assert!((statistics.std_dev().unwrap() - 30f64.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn has_no_deviation_of_single_sample() {
+        let statistics = statistics(&[-1.5]);
+        assert_eq!(statistics.average(), Some(-1.5));
+        assert_eq!((statistics.min, statistics.max), (-1.5, -1.5));
+        assert_eq!(statistics.std_dev(), None);
+        assert_eq!(Statistics::new().average(), None);
+    }
+
+    #[test]
+    fn resets_to_empty() {
+        let mut statistics = statistics(&[1.0, 2.0, 3.0]);
+        statistics.reset();
+        assert_eq!(statistics.count, 0);
+        assert_eq!(statistics.average(), None);
+        assert_eq!(statistics.std_dev(), None);
+        assert!(statistics.min.is_nan() && statistics.max.is_nan());
+        statistics.add_value(4.0);
+        assert_eq!((statistics.min, statistics.max), (4.0, 4.0));
+        assert_eq!(statistics.average(), Some(4.0));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::limits::Zone;
+use raylib::prelude::*;
+use std::fs;
+
+/// Colors of all display elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    pub digits: Color,
+    pub label: Color, // Channel label and reading health
+    pub chart_points: Color,
+    pub axes: Color,
+    pub grid: Color,
+    pub chart_labels: Color,
+    pub histogram: Color,
+    pub zone_pass: Color,
+    pub zone_warning: Color,
+    pub zone_fail: Color,
+}
+
+impl Theme {
+    pub const NAMES: [&'static str; 4] = ["dark", "daylight", "amber", "green_lcd"];
+
+    /// Built-in theme: `dark` (default), `daylight` (high-contrast for bright rooms),
+    /// `amber` (VFD look) or `green_lcd`.
+    pub fn named(name: &str) -> Result<Self, String> {
+        let (background, digits, label, chart_points, axes, grid, chart_labels, histogram) =
+            match name {
+                "dark" => (
+                    Color::BLACK,
+                    Color::RED,
+                    Color::WHITE,
+                    Color::RED,
+                    Color::GRAY,
+                    Color::DARKGRAY,
+                    Color::GRAY,
+                    Color::DARKGRAY,
+                ),
+                "daylight" => (
+                    Color::WHITE,
+                    Color::BLACK,
+                    Color::BLACK,
+                    Color::DARKBLUE,
+                    Color::BLACK,
+                    Color::LIGHTGRAY,
+                    Color::BLACK,
+                    Color::DARKGRAY,
+                ),
+                "amber" => (
+                    Color::new(12, 8, 0, 255),
+                    Color::new(255, 176, 0, 255),
+                    Color::new(255, 204, 102, 255),
+                    Color::new(255, 176, 0, 255),
+                    Color::new(153, 106, 0, 255),
+                    Color::new(64, 44, 0, 255),
+                    Color::new(153, 106, 0, 255),
+                    Color::new(153, 106, 0, 255),
+                ),
+                "green_lcd" => (
+                    Color::new(158, 173, 134, 255),
+                    Color::new(30, 42, 30, 255),
+                    Color::new(30, 42, 30, 255),
+                    Color::new(30, 42, 30, 255),
+                    Color::new(60, 76, 56, 255),
+                    Color::new(130, 145, 108, 255),
+                    Color::new(60, 76, 56, 255),
+                    Color::new(60, 76, 56, 255),
+                ),
+                _ => {
+                    return Err(format!(
+                        "Unknown theme '{}', available themes: {}",
+                        name,
+                        Theme::NAMES.join(", ")
+                    ))
+                }
+            };
+        // Darker zone colors are readable on light backgrounds
+        let (zone_pass, zone_warning, zone_fail) = match name {
+            "daylight" | "green_lcd" => (
+                Color::DARKGREEN,
+                Color::new(200, 110, 0, 255),
+                Color::MAROON,
+            ),
+            _ => (Color::GREEN, Color::ORANGE, Color::RED),
+        };
+        Ok(Theme {
+            background,
+            digits,
+            label,
+            chart_points,
+            axes,
+            grid,
+            chart_labels,
+            histogram,
+            zone_pass,
+            zone_warning,
+            zone_fail,
+        })
+    }
+
+    /// Overrides colors with the ones from a theme file, with one `element = color` per line,
+    /// i.e. `digits = #FFB000`. Empty lines and lines starting with `#` are skipped.
+    pub fn load(&mut self, file_path: &str) -> Result<(), String> {
+        let content = fs::read_to_string(file_path).map_err(|e| format!("{}: {}", file_path, e))?;
+        for (line_no, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: String| format!("{}:{}: {}", file_path, line_no + 1, reason);
+            let (element, color) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected 'element = color'".to_string()))?;
+            let color = parse_color(color).map_err(invalid)?;
+            match element.trim() {
+                "background" => self.background = color,
+                "digits" => self.digits = color,
+                "label" => self.label = color,
+                "chart_points" => self.chart_points = color,
+                "axes" => self.axes = color,
+                "grid" => self.grid = color,
+                "chart_labels" => self.chart_labels = color,
+                "histogram" => self.histogram = color,
+                "zone_pass" => self.zone_pass = color,
+                "zone_warning" => self.zone_warning = color,
+                "zone_fail" => self.zone_fail = color,
+                element => return Err(invalid(format!("unknown theme element '{}'", element))),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn zone_color(&self, zone: Zone) -> Color {
+        match zone {
+            Zone::Pass => self.zone_pass,
+            Zone::Warning => self.zone_warning,
+            Zone::Fail => self.zone_fail,
+        }
+    }
+}
+
+/// Parses a color given as hex `RRGGBB` or `RRGGBBAA` (optionally with `#`), decimal `R,G,B`
+/// or a basic color name (i.e. `red`, `orange`).
+pub fn parse_color(text: &str) -> Result<Color, String> {
+    let text = text.trim();
+    let invalid = || format!("Invalid color '{}'", text);
+    if let Some(color) = named_color(text) {
+        return Ok(color);
+    }
+    if text.contains(',') {
+        let components = text
+            .split(',')
+            .map(|component| component.trim().parse::<u8>())
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        return match components[..] {
+            [r, g, b] => Ok(Color::new(r, g, b, 255)),
+            [r, g, b, a] => Ok(Color::new(r, g, b, a)),
+            _ => Err(invalid()),
+        };
+    }
+    let hex = text.trim_start_matches('#');
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    let alpha = if hex.len() == 8 { component(6)? } else { 255 };
+    Ok(Color::new(
+        component(0)?,
+        component(2)?,
+        component(4)?,
+        alpha,
+    ))
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    match name.to_ascii_lowercase().as_str() {
+        // Short names kept from the first versions of `--color`
+        "r" | "red" => Some(Color::RED),
+        "g" | "green" => Some(Color::GREEN),
+        "b" | "blue" => Some(Color::BLUE),
+        "white" => Some(Color::WHITE),
+        "black" => Some(Color::BLACK),
+        "yellow" => Some(Color::YELLOW),
+        "orange" => Some(Color::ORANGE),
+        "gray" | "grey" => Some(Color::GRAY),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(text: &str) -> Result<(u8, u8, u8, u8), String> {
+        parse_color(text).map(|color| (color.r, color.g, color.b, color.a))
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        assert_eq!(rgba("FFB000"), Ok((255, 176, 0, 255)));
+        assert_eq!(rgba("#ffb00080"), Ok((255, 176, 0, 128)));
+        assert!(rgba("FFB00").is_err());
+        assert!(rgba("GGB000").is_err());
+    }
+
+    #[test]
+    fn parses_decimal_colors() {
+        assert_eq!(rgba("255, 176, 0"), Ok((255, 176, 0, 255)));
+        assert_eq!(rgba("1,2,3,4"), Ok((1, 2, 3, 4)));
+        assert!(rgba("1,2").is_err());
+        assert!(rgba("256,0,0").is_err());
+    }
+
+    #[test]
+    fn parses_color_names() {
+        assert_eq!(rgba("r"), rgba("red"));
+        assert_eq!(rgba("Orange"), Ok((255, 161, 0, 255)));
+        assert!(rgba("purple").is_err());
+    }
+}